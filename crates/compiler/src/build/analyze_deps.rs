@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use toy_farm_core::{
+    error::Result, CompilationContext, CompilationError, ModuleMetaData,
+    PluginAnalyzeDepsHookParam, PluginAnalyzeDepsHookResultEntry,
+};
+use toy_farm_toolkit::{
+    css, html,
+    script::{
+        self,
+        swc_common::{Mark, GLOBALS},
+    },
+};
+
+pub async fn analyze_deps(
+    analyze_deps_param: &PluginAnalyzeDepsHookParam<'_>,
+    context: &Arc<CompilationContext>,
+) -> Result<Vec<PluginAnalyzeDepsHookResultEntry>> {
+    match context
+        .plugin_driver
+        .analyze_deps(analyze_deps_param, context)
+        .await
+    {
+        Ok(Some(deps)) => Ok(deps),
+        // no plugins handle this module, fallback to the built-in analyzers
+        Ok(None) => Ok(builtin_analyze_deps(analyze_deps_param.meta, context)),
+        Err(e) => Err(CompilationError::AnalyzeDepsError {
+            resolved_path: analyze_deps_param.module_id.to_string(),
+            source: Some(Box::new(e)),
+        }),
+    }
+}
+
+fn builtin_analyze_deps(
+    meta: &ModuleMetaData,
+    context: &Arc<CompilationContext>,
+) -> Vec<PluginAnalyzeDepsHookResultEntry> {
+    match meta {
+        ModuleMetaData::Script(script) => GLOBALS.set(&context.meta.script.globals, || {
+            script::analyze_deps::analyze_deps(&script.ast, Mark::from_u32(script.unresolved_mark))
+        }),
+        ModuleMetaData::Css(css) => css::analyze_deps::analyze_deps(&css.ast),
+        ModuleMetaData::Html(html) => html::analyze_deps::analyze_deps(&html.ast),
+        ModuleMetaData::Custom(_) => vec![],
+    }
+}
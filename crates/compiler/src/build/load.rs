@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use toy_farm_core::{
-    error::Result, CompilationContext, CompilationError, PluginLoadHookParam, PluginLoadHookResult,
+    error::Result, CompilationContext, CompilationError, PluginHookContext, PluginLoadHookParam,
+    PluginLoadHookResult,
 };
 
 pub async fn load(
@@ -12,7 +13,7 @@ pub async fn load(
     let loaded = match context
         .clone()
         .plugin_driver
-        .load(load_param, context)
+        .load(load_param, context, Arc::new(PluginHookContext::default()))
         .await
     {
         Ok(loaded) => match loaded {
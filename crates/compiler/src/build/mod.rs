@@ -1,3 +1,4 @@
+mod analyze_deps;
 mod load;
 mod module_cached;
 mod parse;
@@ -5,6 +6,7 @@ mod resolve;
 mod transform;
 use std::sync::Arc;
 
+use analyze_deps::analyze_deps;
 use load::load;
 use parse::parse;
 use resolve::resolve;
@@ -15,18 +17,19 @@ use futures::future::join_all;
 
 use module_cached::{
     get_content_hash_of_module, get_timestamp_of_module, try_get_module_cache_by_hash,
-    try_get_module_cache_by_timestamp,
+    try_get_module_cache_by_timestamp, try_get_module_cache_of_dependency,
 };
 use tokio::{
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     task::JoinHandle,
 };
 use toy_farm_core::{
     error::Result, module::ModuleId, module_cache::CachedModule, plugin::PluginResolveHookResult,
     plugin_driver::PluginDriverTransformHookResult, CompilationContext, CompilationError, Module,
-    ModuleGraph, ModuleGraphEdgeDataItem, ModuleMetaData, ModuleType,
+    ModuleGraph, ModuleGraphEdgeDataItem, ModuleMetaData, ModuleType, PluginAnalyzeDepsHookParam,
     PluginAnalyzeDepsHookResultEntry, PluginLoadHookParam, PluginParseHookParam,
     PluginProcessModuleHookParam, PluginResolveHookParam, PluginTransformHookParam, ResolveKind,
+    VIRTUAL_MODULE_PREFIX,
 };
 
 use toy_farm_utils::stringify_query;
@@ -43,7 +46,7 @@ pub(crate) struct ResolvedModuleInfo {
 enum ResolveModuleResult {
     // The module is already built
     Built(ModuleId),
-    Cached(ModuleId),
+    Cached(Box<CachedModule>),
     Success(Box<ResolvedModuleInfo>),
 }
 
@@ -52,7 +55,7 @@ pub(crate) struct BuildModuleGraphParams {
     pub context: Arc<CompilationContext>,
     pub cached_dependency: Option<ModuleId>,
     pub order: usize,
-    pub err_sender: Sender<CompilationError>,
+    pub err_sender: UnboundedSender<CompilationError>,
 }
 pub(crate) struct HandleDependenciesParams {
     pub module: Module,
@@ -60,12 +63,10 @@ pub(crate) struct HandleDependenciesParams {
     pub order: usize,
     pub deps: Vec<(PluginAnalyzeDepsHookResultEntry, Option<ModuleId>)>,
     // pub thread_pool: Arc<ThreadPool>,
-    pub err_sender: Sender<CompilationError>,
+    pub err_sender: UnboundedSender<CompilationError>,
     pub context: Arc<CompilationContext>,
 }
 
-use self::module_cached::handle_cached_modules;
-
 macro_rules! call_and_catch_error {
     ($func:ident, $param:expr, $context:expr) => {
         match $func($param, $context).await {
@@ -93,15 +94,7 @@ impl Compiler {
         };
 
         // MARK: RESOLVE
-        let resolve_result = match resolve(resolve_param.clone(), context.clone()).await {
-            Ok(result) => result,
-            Err(_) => {
-                // log error
-                return Err(CompilationError::GenericError(
-                    "Failed to resolve module id".to_string(),
-                ));
-            }
-        };
+        let resolve_result = resolve(resolve_param.clone(), context.clone()).await?;
 
         let module_id = get_module_id(&resolve_result);
 
@@ -112,8 +105,9 @@ impl Compiler {
     }
 
     // MARK: BUILD
-    pub async fn build(&self) {
-        let (err_sender, _err_receiver) = Self::create_thread_channel();
+    /// Build the module graph from the entries, the first error of the modules is returned after all the modules are handled
+    pub async fn build(&self) -> Result<()> {
+        let (err_sender, mut err_receiver) = Self::create_thread_channel();
 
         for (order, (name, source)) in self.context.config.input.iter().enumerate() {
             println!("Index: {}, Name: {}, Source: {}", order, name, source);
@@ -134,6 +128,15 @@ impl Compiler {
 
             Compiler::build_module_graph(build_module_graph_params).await;
         }
+
+        // wait for the errors sent by the spawned tasks, `recv` returns None once all the senders are dropped
+        drop(err_sender);
+
+        if let Some(e) = err_receiver.recv().await {
+            return Err(e);
+        }
+
+        Ok(())
     }
 
     pub(crate) fn create_module(module_id: ModuleId, external: bool, immutable: bool) -> Module {
@@ -172,7 +175,7 @@ impl Compiler {
                 Ok(result) => result,
                 Err(e) => {
                     // log error
-                    err_sender.send(e).await.unwrap();
+                    err_sender.send(e).unwrap();
                     return;
                 }
             };
@@ -202,7 +205,7 @@ impl Compiler {
                 .await
                 {
                     Err(e) => {
-                        err_sender.send(e).await.unwrap();
+                        err_sender.send(e).unwrap();
                     }
                     Ok(deps) => {
                         let params = HandleDependenciesParams {
@@ -221,19 +224,13 @@ impl Compiler {
                 // handle the built module
                 Self::add_edge(&resolve_param, module_id, order, &context).await;
             }
-            ResolveModuleResult::Cached(module_id) => {
-                // handle the cached module
-                let mut cached_module = context.cache_manager.module_cache.get_cache(&module_id);
-                if let Err(e) = handle_cached_modules(&mut cached_module, &context).await {
-                    err_sender.send(e).await.unwrap();
-                };
-
+            ResolveModuleResult::Cached(cached_module) => {
+                // handle the cached module accepted by `handle_persistent_cached_module`
                 let params = HandleDependenciesParams {
                     module: cached_module.module,
                     resolve_param,
                     order,
                     deps: CachedModule::dep_sources(cached_module.dependencies),
-                    // err_sender,
                     context,
                     err_sender,
                 };
@@ -286,11 +283,12 @@ impl Compiler {
         }
     }
 
-    pub(crate) fn create_thread_channel() -> (Sender<CompilationError>, Receiver<CompilationError>)
-    {
-        let (err_sender, err_receiver) = channel::<CompilationError>(1024);
-
-        (err_sender, err_receiver)
+    /// The errors are drained after all the build tasks are finished, the channel is unbounded so that sending never blocks the tasks.
+    pub(crate) fn create_thread_channel() -> (
+        UnboundedSender<CompilationError>,
+        UnboundedReceiver<CompilationError>,
+    ) {
+        unbounded_channel::<CompilationError>()
     }
 
     /// Resolving, loading, transforming and parsing a module, return the module and its dependencies if success
@@ -303,6 +301,15 @@ impl Compiler {
 
         module.last_update_timestamp = if module.immutable {
             0
+        } else if let Some(virtual_module) = module
+            .id
+            .relative_path()
+            .starts_with(VIRTUAL_MODULE_PREFIX)
+            .then(|| context.virtual_modules.get(module.id.relative_path()))
+            .flatten()
+        {
+            // registered virtual modules do not exist on the disk, use the time they are updated
+            virtual_module.updated_at
         } else {
             get_timestamp_of_module(&module.id, &context.config.root)
         };
@@ -393,17 +400,16 @@ impl Compiler {
             call_and_catch_error!(parse, Arc::new(parse_param.clone()), context);
 
         // MARK: PROCESS MODULE
+        let mut process_module_param = PluginProcessModuleHookParam {
+            module_id: &parse_param.module_id,
+            module_type: &parse_param.module_type,
+            content: module.content.clone(),
+            meta: &mut module_meta,
+        };
+
         if let Err(e) = context
             .plugin_driver
-            .process_module(
-                &mut PluginProcessModuleHookParam {
-                    module_id: &parse_param.module_id,
-                    module_type: &parse_param.module_type,
-                    content: module.content.clone(),
-                    meta: &mut module_meta,
-                },
-                context,
-            )
+            .process_module(&mut process_module_param, context)
             .await
         {
             return Err(CompilationError::ProcessModuleError {
@@ -412,12 +418,28 @@ impl Compiler {
             });
         }
 
-        module.size = parse_param.content.as_bytes().len();
+        context
+            .ast_passes
+            .apply(&mut process_module_param, &context.meta.script.globals);
+
+        // MARK: ANALYZE DEPS
+        let deps = call_and_catch_error!(
+            analyze_deps,
+            &PluginAnalyzeDepsHookParam {
+                module_id: &parse_param.module_id,
+                resolved_path: &parse_param.resolved_path,
+                module_type: &parse_param.module_type,
+                meta: &module_meta,
+            },
+            context
+        );
+
+        module.size = parse_param.content.len();
         module.module_type = parse_param.module_type;
         module.side_effects = resolve_result.side_effects;
         module.external = false;
         module.source_map_chain = transform_result.source_map_chain;
-        module.meta = Box::new(module_meta);
+        *module.meta = module_meta;
 
         let _resolved_path = module.id.resolved_path(&context.config.root);
         // let package_info =
@@ -425,33 +447,10 @@ impl Compiler {
         // module.package_name = package_info.name.unwrap_or("default".to_string());
         // module.package_version = package_info.version.unwrap_or("0.0.0".to_string());
 
-        Ok(vec![])
+        Ok(deps)
     }
 }
 
-fn handle_cached_dependency(
-    cached_dependency: &ModuleId,
-    module_graph: &mut ModuleGraph,
-    context: &Arc<CompilationContext>,
-) -> Result<Option<ResolveModuleResult>> {
-    let module_cache_manager = &context.cache_manager.module_cache;
-
-    if module_cache_manager.has_cache(cached_dependency) {
-        // todo: to finish plugin driver and handle persistent cache
-        let _cached_module = module_cache_manager.get_cache_ref(cached_dependency);
-        let should_invalidate_cached_module = true;
-
-        if should_invalidate_cached_module {
-            module_cache_manager.invalidate_cache(cached_dependency);
-        } else {
-            Compiler::insert_dummy_module(cached_dependency, module_graph);
-            return Ok(Some(ResolveModuleResult::Cached(cached_dependency.clone())));
-        }
-    }
-
-    Ok(None)
-}
-
 // This function spawns a task for a single dependency
 fn spawn_dependency_task(
     params: BuildModuleGraphParams,
@@ -463,7 +462,7 @@ fn spawn_dependency_task(
 }
 
 // MARK: HANDLE DEPENDENCIES
-async fn handle_dependencies(params: HandleDependenciesParams) {
+pub(crate) async fn handle_dependencies(params: HandleDependenciesParams) {
     let HandleDependenciesParams {
         module,
         resolve_param,
@@ -511,12 +510,9 @@ async fn handle_dependencies(params: HandleDependenciesParams) {
             Err(join_error) => Some(CompilationError::from(join_error)),
         })
         .for_each(|error| {
-            let err_sender = err_sender.clone();
-            tokio::spawn(async move {
-                if let Err(e) = err_sender.send(error).await {
-                    eprintln!("Failed to send error: {:?}", e);
-                }
-            });
+            if let Err(e) = err_sender.send(error) {
+                eprintln!("Failed to send error: {:?}", e);
+            }
         });
 }
 
@@ -534,6 +530,21 @@ async fn resolve_module(
         resolve_module_id_result.as_ref().unwrap().module_id.clone()
     };
 
+    // the module graph is not locked while the plugins check the cached dependency
+    if cached_dependency.is_some() && !context.module_graph.read().await.has_module(&module_id) {
+        if let Some(cached_module) = try_get_module_cache_of_dependency(&module_id, context).await?
+        {
+            let mut module_graph = context.module_graph.write().await;
+
+            if module_graph.has_module(&module_id) {
+                return Ok(ResolveModuleResult::Built(module_id));
+            }
+
+            Compiler::insert_dummy_module(&module_id, &mut module_graph);
+            return Ok(ResolveModuleResult::Cached(Box::new(cached_module)));
+        }
+    }
+
     let mut module_graph: tokio::sync::RwLockWriteGuard<ModuleGraph> =
         context.module_graph.write().await;
 
@@ -541,14 +552,6 @@ async fn resolve_module(
         return Ok(ResolveModuleResult::Built(module_id));
     }
 
-    if let Some(cached_dependency) = cached_dependency {
-        if let Some(result) =
-            handle_cached_dependency(&cached_dependency, &mut module_graph, context)?
-        {
-            return Ok(result);
-        }
-    }
-
     let resolve_module_id_result = if let Some(result) = resolve_module_id_result {
         result
     } else {
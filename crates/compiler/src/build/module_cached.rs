@@ -70,8 +70,10 @@ pub async fn try_get_module_cache_by_hash(
             if cached_module.module.immutable
                 || !is_watch_dependencies_content_hash_changed(&cached_module, context).await
             {
-                // TODO: handle persistent cached module
-                let should_invalidate_cached_module = false;
+                let should_invalidate_cached_module = context
+                    .plugin_driver
+                    .handle_persistent_cached_module(&mut cached_module.module, context)
+                    .await?;
 
                 if !should_invalidate_cached_module {
                     return Ok(Some(cached_module));
@@ -114,8 +116,10 @@ pub async fn try_get_module_cache_by_timestamp(
             if cached_module.module.immutable
                 || !is_watch_dependencies_timestamp_changed(&cached_module, &context).await
             {
-                // TODO: handle persistent cached module
-                let should_invalidate_cached_module = false;
+                let should_invalidate_cached_module = context
+                    .plugin_driver
+                    .handle_persistent_cached_module(&mut cached_module.module, &context)
+                    .await?;
 
                 if !should_invalidate_cached_module {
                     return Ok(Some(cached_module));
@@ -137,6 +141,34 @@ pub async fn try_get_module_cache_by_timestamp(
 
     Ok(None)
 }
+
+/// Get the cached dependency of an immutable module, the cache is invalidated if it is rejected by `handle_persistent_cached_module`.
+pub async fn try_get_module_cache_of_dependency(
+    module_id: &ModuleId,
+    context: &Arc<CompilationContext>,
+) -> Result<Option<CachedModule>> {
+    let module_cache = &context.cache_manager.module_cache;
+
+    if !module_cache.has_cache(module_id) {
+        return Ok(None);
+    }
+
+    let mut cached_module = module_cache.get_cache(module_id);
+    handle_cached_modules(&mut cached_module, context).await?;
+
+    let should_invalidate_cached_module = context
+        .plugin_driver
+        .handle_persistent_cached_module(&mut cached_module.module, context)
+        .await?;
+
+    if should_invalidate_cached_module {
+        module_cache.invalidate_cache(module_id);
+        return Ok(None);
+    }
+
+    Ok(Some(cached_module))
+}
+
 async fn handle_relation_roots(
     cached_module_id: &ModuleId,
     watch_dependencies: &[CachedWatchDependency],
@@ -169,7 +201,8 @@ pub async fn handle_cached_modules(
         }
         ModuleMetaData::Css { .. } => { /* 不做任何事 */ }
         ModuleMetaData::Html { .. } => { /* 不做任何事 */ }
-        ModuleMetaData::Custom { .. } => { /* TODO: 为自定义模块添加一个钩子 */ }
+        // 自定义模块的元数据由插件的 `handle_persistent_cached_module` 钩子处理
+        ModuleMetaData::Custom { .. } => { /* 不做任何事 */ }
     };
 
     handle_relation_roots(
@@ -249,3 +282,146 @@ async fn is_watch_dependencies_content_hash_changed(
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+    use toy_farm_core::{
+        error::Result, module_cache::CachedModule, persistent_cache::PersistentCacheConfig,
+        CompilationContext, Config, Module, ModuleId, Plugin,
+    };
+
+    use super::{
+        try_get_module_cache_by_hash, try_get_module_cache_by_timestamp,
+        try_get_module_cache_of_dependency,
+    };
+
+    struct CachedModulePlugin {
+        reject: bool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Plugin for CachedModulePlugin {
+        fn name(&self) -> &str {
+            "CachedModulePlugin"
+        }
+
+        async fn handle_persistent_cached_module(
+            &self,
+            module: &mut Module,
+            _context: &Arc<CompilationContext>,
+        ) -> Result<Option<bool>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            if self.reject {
+                return Ok(Some(true));
+            }
+
+            module.used_exports = vec!["restored".to_string()];
+            Ok(None)
+        }
+    }
+
+    /// a context with `a.js` cached, whose timestamp is `1` and content hash is `hash`
+    fn create_context(reject: bool) -> (Arc<CompilationContext>, Arc<CachedModulePlugin>) {
+        let plugin = Arc::new(CachedModulePlugin {
+            reject,
+            calls: AtomicUsize::new(0),
+        });
+        let config = Config {
+            root: std::env::temp_dir()
+                .join("toy_farm_module_cached")
+                .to_string_lossy()
+                .to_string(),
+            persistent_cache: Box::new(PersistentCacheConfig::Bool(true)),
+            ..Default::default()
+        };
+        let context = Arc::new(CompilationContext::new(config, vec![plugin.clone()]).unwrap());
+
+        let mut module = Module::new("a.js".into());
+        module.last_update_timestamp = 1;
+        module.content_hash = "hash".to_string();
+        context.cache_manager.module_cache.set_cache(
+            "a.js".into(),
+            CachedModule {
+                module,
+                dependencies: vec![],
+                watch_dependencies: vec![],
+            },
+        );
+
+        (context, plugin)
+    }
+
+    #[tokio::test]
+    async fn accepted_cached_module() {
+        let (context, plugin) = create_context(false);
+        let module_id: ModuleId = "a.js".into();
+
+        // a mismatched timestamp falls back to the content hash, the cache is kept
+        let cached_module = try_get_module_cache_by_timestamp(&module_id, 2, context.clone())
+            .await
+            .unwrap();
+        assert!(cached_module.is_none());
+        assert!(context.cache_manager.module_cache.has_cache(&module_id));
+
+        let cached_module = try_get_module_cache_by_timestamp(&module_id, 1, context.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached_module.module.used_exports, vec!["restored"]);
+
+        assert_eq!(plugin.calls.load(Ordering::SeqCst), 1);
+
+        // the cached module is taken out of the store, create another context for each lookup
+        let (context, plugin) = create_context(false);
+        let cached_module = try_get_module_cache_by_hash(&module_id, "hash", &context)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached_module.module.used_exports, vec!["restored"]);
+        assert_eq!(plugin.calls.load(Ordering::SeqCst), 1);
+
+        let (context, plugin) = create_context(false);
+        let cached_module = try_get_module_cache_of_dependency(&module_id, &context)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached_module.module.used_exports, vec!["restored"]);
+        assert_eq!(plugin.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_cached_module() {
+        let (context, plugin) = create_context(true);
+        let module_id: ModuleId = "a.js".into();
+
+        let cached_module = try_get_module_cache_by_timestamp(&module_id, 1, context.clone())
+            .await
+            .unwrap();
+        assert!(cached_module.is_none());
+        assert!(!context.cache_manager.module_cache.has_cache(&module_id));
+
+        let (context, _) = create_context(true);
+        let cached_module = try_get_module_cache_by_hash(&module_id, "hash", &context)
+            .await
+            .unwrap();
+        assert!(cached_module.is_none());
+        assert!(!context.cache_manager.module_cache.has_cache(&module_id));
+
+        let (context, _) = create_context(true);
+        let cached_module = try_get_module_cache_of_dependency(&module_id, &context)
+            .await
+            .unwrap();
+        assert!(cached_module.is_none());
+        assert!(!context.cache_manager.module_cache.has_cache(&module_id));
+
+        assert_eq!(plugin.calls.load(Ordering::SeqCst), 1);
+    }
+}
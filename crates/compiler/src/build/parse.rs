@@ -24,6 +24,8 @@ pub async fn parse(
                 ),
             }),
         },
+        // the error is already reported with the position by plugins
+        Err(e @ CompilationError::ParseError { .. }) => Err(e),
         Err(e) => Err(CompilationError::ParseError {
             resolved_path: parse_param.module_id.to_string(),
             msg: e.to_string(),
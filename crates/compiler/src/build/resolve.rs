@@ -2,7 +2,9 @@ use std::sync::Arc;
 
 use toy_farm_core::error::Result;
 use toy_farm_core::plugin::PluginResolveHookResult;
-use toy_farm_core::{CompilationContext, CompilationError, PluginResolveHookParam};
+use toy_farm_core::{
+    CompilationContext, CompilationError, PluginHookContext, PluginResolveHookParam,
+};
 
 pub async fn resolve(
     resolve_param: PluginResolveHookParam,
@@ -16,7 +18,11 @@ pub async fn resolve(
 
     let resolved = match context
         .plugin_driver
-        .resolve(Arc::new(resolve_param.clone()), context.clone())
+        .resolve(
+            Arc::new(resolve_param.clone()),
+            context.clone(),
+            Arc::new(PluginHookContext::default()),
+        )
         .await
     {
         Ok(resolved) => match resolved {
@@ -29,6 +35,8 @@ pub async fn resolve(
                 });
             }
         },
+        // the plugin explains why the source can not be resolved
+        Err(e @ CompilationError::ResolveError { .. }) => return Err(e),
         Err(e) => {
             return Err(CompilationError::ResolveError {
                 importer,
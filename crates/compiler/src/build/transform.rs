@@ -2,22 +2,34 @@ use std::sync::Arc;
 
 use toy_farm_core::{
     error::Result, plugin_driver::PluginDriverTransformHookResult, CompilationContext,
-    CompilationError, PluginTransformHookParam,
+    CompilationError, PluginHookContext, PluginTransformHookParam,
 };
+use toy_farm_toolkit::sourcemap::collapse_sourcemap_chain;
 
 pub async fn transform(
     transform_param: PluginTransformHookParam,
     context: Arc<CompilationContext>,
 ) -> Result<PluginDriverTransformHookResult> {
     let module_id = transform_param.module_id.to_string();
-    let transformed = context
+    let to_transform_error = |e: CompilationError| CompilationError::TransformError {
+        resolved_path: module_id.clone(),
+        msg: e.to_string(),
+    };
+    let mut transformed = context
         .plugin_driver
-        .transform(transform_param, context.clone())
+        .transform(
+            transform_param,
+            context.clone(),
+            Arc::new(PluginHookContext::default()),
+        )
         .await
-        .map_err(|e| CompilationError::TransformError {
-            resolved_path: module_id,
-            msg: e.to_string(),
-        })?;
+        .map_err(to_transform_error)?;
+
+    if context.config.sourcemap.collapse && transformed.source_map_chain.len() > 1 {
+        let collapsed =
+            collapse_sourcemap_chain(&transformed.source_map_chain).map_err(to_transform_error)?;
+        transformed.source_map_chain = vec![Arc::new(collapsed)];
+    }
 
     Ok(transformed)
 }
@@ -0,0 +1,38 @@
+use toy_farm_core::{error::Result, PluginGenerateResourcesHookParam};
+
+use crate::Compiler;
+
+impl Compiler {
+    // MARK: GENERATE
+    /// Generate resources for each entry of the module graph, the generated resources are stored in [toy_farm_core::CompilationContext::resources_map].
+    /// Must be called after the module graph is built.
+    pub async fn generate(&self) -> Result<()> {
+        let mut module_graph = self.context.module_graph.write().await;
+        module_graph.update_execution_order_for_modules();
+        let module_graph = module_graph.downgrade();
+        // resources of the previous compilation are outdated
+        self.context.resources_map.lock().await.clear();
+
+        // sort entries to make sure the generated resources are stable
+        let mut entries = module_graph.entries.iter().collect::<Vec<_>>();
+        entries.sort();
+
+        for (entry, entry_name) in entries {
+            let modules = module_graph.static_reachable_modules(entry);
+            self.context
+                .plugin_driver
+                .generate_resources(
+                    &PluginGenerateResourcesHookParam {
+                        entry_name,
+                        entry,
+                        modules: &modules,
+                        module_graph: &module_graph,
+                    },
+                    &self.context,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,26 +1,98 @@
-use std::{sync::Arc, vec};
+use std::{path::Path, sync::Arc, vec};
 
-use toy_farm_core::{CompilationContext, Config};
+use toy_farm_core::{
+    error::Result, native_plugin::load_native_plugin, plugin_driver::PluginDriver,
+    process_plugin::ProcessPlugin, wasm_plugin::WasmPlugin, CompilationContext, Config,
+};
+use toy_farm_plugin_css::FarmPluginCss;
+use toy_farm_plugin_html::FarmPluginHtml;
+use toy_farm_plugin_load::FarmPluginLoad;
 use toy_farm_plugin_resolve::FarmPluginResolve;
+use toy_farm_plugin_script::FarmPluginScript;
+use toy_farm_plugin_virtual_modules::FarmPluginVirtualModules;
 
 pub mod build;
+pub mod generate;
+pub mod update;
 
 pub struct Compiler {
     context: Arc<CompilationContext>,
 }
 
 impl Compiler {
-    pub async fn new(config: Config) -> Compiler {
-        let plugins = vec![Arc::new(FarmPluginResolve::new(&config)) as _];
+    pub async fn new(mut config: Config) -> Result<Compiler> {
+        let mut plugins = vec![
+            Arc::new(FarmPluginResolve::new(&config)?) as _,
+            Arc::new(FarmPluginLoad::new()) as _,
+            Arc::new(FarmPluginScript::new()) as _,
+            Arc::new(FarmPluginCss::new()) as _,
+            Arc::new(FarmPluginHtml::new()) as _,
+            Arc::new(FarmPluginVirtualModules::new()) as _,
+        ];
 
-        let mut context = CompilationContext::new(config, plugins);
-        let _ = context.plugin_driver.config(&mut context.config).await;
-        Compiler {
+        for plugin_config in &config.native_plugins {
+            let path = Path::new(&config.root).join(&plugin_config.path);
+            plugins.push(load_native_plugin(
+                &path,
+                &config,
+                plugin_config.options.clone(),
+            )?);
+        }
+
+        for plugin_config in &config.process_plugins {
+            plugins.push(Arc::new(ProcessPlugin::new(plugin_config, &config.root).await?) as _);
+        }
+
+        for plugin_config in &config.wasm_plugins {
+            plugins.push(Arc::new(WasmPlugin::new(plugin_config, &config.root)?) as _);
+        }
+
+        let plugins = PluginDriver::resolve_config(plugins, &mut config).await?;
+        let context = CompilationContext::new(config, plugins)?;
+        context
+            .plugin_driver
+            .config_resolved(&context.config)
+            .await?;
+
+        Ok(Compiler {
             context: Arc::new(context),
+        })
+    }
+
+    pub async fn compile(&self) -> Result<()> {
+        let plugin_driver = &self.context.plugin_driver;
+        let persistent_cache = self.context.config.persistent_cache.enabled();
+
+        if persistent_cache {
+            plugin_driver.plugin_cache_loaded(&self.context).await?;
         }
+
+        plugin_driver.build_start(&self.context).await?;
+        self.build().await?;
+        plugin_driver.build_end(&self.context).await?;
+
+        {
+            let mut module_graph = self.context.module_graph.write().await;
+            plugin_driver
+                .optimize_module_graph(&mut module_graph, &self.context)
+                .await?;
+        }
+
+        plugin_driver.generate_start(&self.context).await?;
+        self.generate().await?;
+        plugin_driver.generate_end(&self.context).await?;
+
+        plugin_driver.finish(&self.context).await?;
+
+        if persistent_cache {
+            plugin_driver.write_plugin_cache(&self.context).await?;
+            self.context.cache_manager.write_cache().await;
+        }
+
+        Ok(())
     }
 
-    pub async fn compile(&self) {
-        self.build().await;
+    pub fn context(&self) -> &Arc<CompilationContext> {
+        &self.context
     }
 }
@@ -0,0 +1,248 @@
+use std::{collections::HashSet, path::Path};
+
+use toy_farm_core::{
+    error::Result, record::Trigger, ModuleId, PluginModuleGraphUpdatedHookParam,
+    PluginResolveHookParam, PluginResolveHookResult, PluginUpdateModulesHookParam, ResolveKind,
+    UpdateType, VIRTUAL_MODULE_PREFIX,
+};
+use toy_farm_utils::{canonicalize_path, parse_query};
+
+use crate::{
+    build::{handle_dependencies, HandleDependenciesParams},
+    Compiler,
+};
+
+#[derive(Debug, Default)]
+pub struct UpdateResult {
+    pub added_module_ids: Vec<ModuleId>,
+    pub updated_module_ids: Vec<ModuleId>,
+    pub removed_module_ids: Vec<ModuleId>,
+}
+
+impl Compiler {
+    // MARK: UPDATE
+    /// Rebuild the modules of the changed files, update the module graph and regenerate the resources.
+    /// Records written during the update, including the records of the regeneration, are tagged with [Trigger::Update].
+    pub async fn update(&self, paths: Vec<(String, UpdateType)>) -> Result<UpdateResult> {
+        let record_manager = &self.context.record_manager;
+
+        record_manager.set_trigger(Trigger::Update).await;
+        let result = async {
+            let result = self.update_module_graph(paths).await?;
+            self.generate().await?;
+
+            Ok(result)
+        }
+        .await;
+        record_manager.set_trigger(Trigger::Compiler).await;
+
+        result
+    }
+
+    /// The updated modules are built before the module graph is touched, so that a failed build leaves the module graph as it is.
+    /// Errors of resolving the dependencies are returned after the module graph is updated.
+    async fn update_module_graph(&self, paths: Vec<(String, UpdateType)>) -> Result<UpdateResult> {
+        let context = &self.context;
+        let mut param = PluginUpdateModulesHookParam { paths };
+        context
+            .plugin_driver
+            .update_modules(&mut param, context)
+            .await?;
+
+        let mut updated_module_ids = vec![];
+        let mut removed_module_ids = vec![];
+
+        let previous_module_ids = {
+            let module_graph = context.module_graph.read().await;
+            let watch_graph = context.watch_graph.read().await;
+            let previous_module_ids = module_graph
+                .modules()
+                .into_iter()
+                .map(|module| module.id.clone())
+                .collect::<HashSet<_>>();
+
+            for (path, update_type) in &param.paths {
+                let file = self.module_id_of_path(path);
+
+                for module_id in module_graph.module_ids_by_file(&file) {
+                    if *update_type == UpdateType::Removed {
+                        // the dependents import the removed module, rebuild them
+                        for (dependent, _) in module_graph.dependents(&module_id) {
+                            updated_module_ids.push(dependent);
+                        }
+
+                        removed_module_ids.push(module_id);
+                    } else {
+                        updated_module_ids.push(module_id);
+                    }
+                }
+
+                // files watched by modules are not modules, e.g. tsconfig.json, rebuild the modules watching them
+                for root in watch_graph.relation_roots(&file) {
+                    if module_graph.has_module(root) {
+                        updated_module_ids.push(root.clone());
+                    }
+                }
+            }
+
+            previous_module_ids
+        };
+
+        let mut seen = HashSet::new();
+        updated_module_ids.retain(|id| !removed_module_ids.contains(id) && seen.insert(id.clone()));
+
+        let mut built_modules = vec![];
+
+        for module_id in &updated_module_ids {
+            let (side_effects, immutable) = {
+                let module_graph = context.module_graph.read().await;
+
+                let Some(module) = module_graph.module(module_id) else {
+                    continue;
+                };
+
+                if module.external {
+                    continue;
+                }
+
+                (module.side_effects, module.immutable)
+            };
+
+            let resolve_result = PluginResolveHookResult {
+                resolved_path: module_id.resolved_path(&context.config.root),
+                query: parse_query(module_id.query_string()),
+                side_effects,
+                ..Default::default()
+            };
+            let mut module = Self::create_module(module_id.clone(), false, immutable);
+            let deps = Self::build_module(resolve_result, &mut module, context.clone()).await?;
+            built_modules.push((module, deps));
+        }
+
+        // dependencies of the updated and removed modules, removed if they are no longer imported
+        let mut orphan_candidates = vec![];
+
+        {
+            let mut module_graph = context.module_graph.write().await;
+
+            for module_id in &removed_module_ids {
+                for (dependency, _) in module_graph.dependencies(module_id) {
+                    orphan_candidates.push(dependency);
+                }
+
+                module_graph.remove_module(module_id);
+            }
+
+            // the edges are added back when the dependencies of the rebuilt modules are handled
+            for (module, _) in &built_modules {
+                let dependencies = module_graph
+                    .dependencies(&module.id)
+                    .into_iter()
+                    .map(|(dependency, _)| dependency)
+                    .collect::<Vec<_>>();
+
+                for dependency in dependencies {
+                    module_graph.remove_edge(&module.id, &dependency);
+                    orphan_candidates.push(dependency);
+                }
+            }
+        }
+
+        let (err_sender, mut err_receiver) = Self::create_thread_channel();
+
+        for (module, deps) in built_modules {
+            let source = module.id.to_string();
+
+            handle_dependencies(HandleDependenciesParams {
+                module,
+                resolve_param: PluginResolveHookParam {
+                    source,
+                    importer: None,
+                    kind: ResolveKind::HmrUpdate,
+                },
+                order: 0,
+                deps,
+                err_sender: err_sender.clone(),
+                context: context.clone(),
+            })
+            .await;
+        }
+
+        // wait for the errors sent by the spawned tasks, `recv` returns None once all the senders are dropped
+        drop(err_sender);
+        let error = err_receiver.recv().await;
+
+        let mut module_graph = context.module_graph.write().await;
+
+        while let Some(module_id) = orphan_candidates.pop() {
+            if !module_graph.has_module(&module_id)
+                || module_graph.entries.contains_key(&module_id)
+                || !module_graph.dependents(&module_id).is_empty()
+            {
+                continue;
+            }
+
+            for (dependency, _) in module_graph.dependencies(&module_id) {
+                orphan_candidates.push(dependency);
+            }
+
+            module_graph.remove_module(&module_id);
+        }
+
+        let current_module_ids = module_graph
+            .modules()
+            .into_iter()
+            .map(|module| module.id.clone())
+            .collect::<HashSet<_>>();
+        drop(module_graph);
+
+        let mut result = UpdateResult {
+            added_module_ids: current_module_ids
+                .difference(&previous_module_ids)
+                .cloned()
+                .collect(),
+            updated_module_ids: updated_module_ids
+                .into_iter()
+                .filter(|id| current_module_ids.contains(id))
+                .collect(),
+            removed_module_ids: previous_module_ids
+                .difference(&current_module_ids)
+                .cloned()
+                .collect(),
+        };
+        result.added_module_ids.sort();
+        result.removed_module_ids.sort();
+
+        context
+            .plugin_driver
+            .module_graph_updated(
+                &PluginModuleGraphUpdatedHookParam {
+                    added_modules_ids: &result.added_module_ids,
+                    removed_modules_ids: &result.removed_module_ids,
+                    updated_modules_ids: &result.updated_module_ids,
+                },
+                context,
+            )
+            .await?;
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        Ok(result)
+    }
+
+    /// Module ids are the resolved paths of the modules, paths relative to the root are joined with the root.
+    /// The path is canonicalized the same way as the resolver does, so that it matches the module id, even if the file is removed.
+    fn module_id_of_path(&self, path: &str) -> ModuleId {
+        if path.starts_with(VIRTUAL_MODULE_PREFIX) {
+            return ModuleId::new(path, "");
+        }
+
+        let path = canonicalize_path(
+            &Path::new(&self.context.config.root).join(path),
+            self.context.config.resolve.symlinks,
+        );
+        ModuleId::new(&path.to_string_lossy(), "")
+    }
+}
@@ -0,0 +1,56 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use toy_farm_core::CompilationError;
+mod common;
+use common::{create_compiler, create_dir, write};
+
+#[tokio::test]
+async fn build_error() {
+    let dir = create_dir("toy_farm_build_error");
+
+    write(&dir, "index.css", "@import 'a.css';");
+    write(&dir, "a.css", "@import 'missing.css';");
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+
+    // the error of a nested dependency fails the compilation
+    match compiler.compile().await {
+        Err(CompilationError::ResolveError { src, .. }) => assert_eq!(src, "missing.css"),
+        result => panic!("expected a resolve error, got {:?}", result),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn many_build_errors() {
+    let dir = create_dir("toy_farm_many_build_errors");
+
+    // more errors than any bounded channel would buffer before the errors are drained
+    let imports = (0..2048)
+        .map(|i| format!("@import 'missing-{}.css';", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    write(&dir, "index.css", &imports);
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+
+    match compiler.compile().await {
+        Err(CompilationError::ResolveError { src, .. }) => assert!(src.starts_with("missing-")),
+        result => panic!("expected a resolve error, got {:?}", result),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
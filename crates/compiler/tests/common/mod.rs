@@ -1,15 +1,38 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use toy_farm_compiler::Compiler;
 use toy_farm_core::{
     config_regex::ConfigRegex, persistent_cache::PersistentCacheConfig, Config, Mode,
 };
 
+/// create an empty temp dir, the temp dir may be a symlink like `/tmp -> /private/tmp` on macos, so it is canonicalized
+/// to match the module ids
+#[allow(dead_code)]
+pub fn create_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dir.canonicalize().unwrap()
+}
+
+/// write the file relative to `dir`, creating its parent directories, return the absolute path
+#[allow(dead_code)]
+pub fn write(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, content).unwrap();
+    path.to_string_lossy().to_string()
+}
+
 pub async fn create_compiler(
     input: HashMap<String, String>,
     cwd: PathBuf,
     _crate_path: PathBuf,
-    _minify: bool,
+    minify: bool,
 ) -> Compiler {
     Compiler::new(Config {
         input,
@@ -27,9 +50,10 @@ pub async fn create_compiler(
         // sourcemap: SourcemapConfig::Bool(false),
         // lazy_compilation: false,
         // progress: false,
-        // minify: Box::new(BoolOrObj::from(minify)),
+        minify,
         // preset_env: Box::new(PresetEnvConfig::Bool(false)),
         ..Default::default()
     })
     .await
+    .unwrap()
 }
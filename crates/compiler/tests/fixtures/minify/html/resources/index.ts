@@ -0,0 +1 @@
+document.getElementById('root').innerHTML = 'hello';
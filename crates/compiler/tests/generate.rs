@@ -0,0 +1,116 @@
+use std::{collections::HashMap, path::PathBuf};
+
+mod common;
+use common::{create_compiler, create_dir, write};
+
+#[tokio::test]
+async fn script_resource() {
+    let dir = create_dir("toy_farm_generate_script");
+
+    write(
+        &dir,
+        "index.ts",
+        "import './index.css';\nimport { add } from './dep';\nexport * from './dep';\nconsole.log(add(1, 2));",
+    );
+    write(
+        &dir,
+        "dep.ts",
+        "export const add = (a: number, b: number): number => a + b;",
+    );
+    write(&dir, "index.css", "body { color: red; }");
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.ts".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    let resources_map = compiler.context().resources_map.lock().await;
+    let js = String::from_utf8(resources_map["index.js"].bytes.clone()).unwrap();
+
+    // the entry is executed by the runtime, and its dependencies are required by their keys
+    assert!(js.contains(r#"require("index.ts");"#), "{}", js);
+    assert!(
+        js.contains(r#""index.ts":function(module,exports,require)"#),
+        "{}",
+        js
+    );
+    assert!(
+        js.contains(r#""dep.ts":function(module,exports,require)"#),
+        "{}",
+        js
+    );
+    assert!(js.contains(r#"require("dep.ts")"#), "{}", js);
+    // the css is bundled into its own resource, types and esm syntax are stripped
+    assert!(!js.contains("index.css"), "{}", js);
+    assert!(!js.contains("number"), "{}", js);
+    assert!(!js.contains("import "), "{}", js);
+    assert!(resources_map.contains_key("index.css"));
+
+    drop(resources_map);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn dynamic_import() {
+    let dir = create_dir("toy_farm_generate_dynamic_import");
+
+    write(
+        &dir,
+        "index.ts",
+        "import('./lazy').then(({ value }) => console.log(value));",
+    );
+    write(
+        &dir,
+        "lazy.ts",
+        "import { dep } from './dep';\nexport const value: number = dep + 1;",
+    );
+    write(&dir, "dep.ts", "export const dep = 41;");
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.ts".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    let resources_map = compiler.context().resources_map.lock().await;
+    let js = String::from_utf8(resources_map["index.js"].bytes.clone()).unwrap();
+
+    // the dynamically imported module and its dependencies are registered in the resource of the entry
+    assert!(
+        js.contains(r#""lazy.ts":function(module,exports,require)"#),
+        "{}",
+        js
+    );
+    assert!(
+        js.contains(r#""dep.ts":function(module,exports,require)"#),
+        "{}",
+        js
+    );
+    drop(resources_map);
+
+    let output_path = write(&dir, "output.js", &js);
+
+    match std::process::Command::new("node")
+        .arg(&output_path)
+        .output()
+    {
+        Ok(output) => {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "42");
+        }
+        Err(e) => eprintln!("node is not available, skip running the resource: {}", e),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
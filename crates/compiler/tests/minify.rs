@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use toy_farm_core::ResourceType;
 use toy_farm_testing_helpers::fixture;
 mod common;
 use common::create_compiler;
@@ -21,9 +22,100 @@ async fn minify_script_test() {
             )
             .await;
 
-            compiler.compile().await
+            compiler.compile().await.unwrap();
 
             //   assert_compiler_result(&compiler, Some(&entry_name));
         }
     );
 }
+
+#[tokio::test]
+async fn minify_css_test() {
+    fixture!(
+        "tests/fixtures/minify/css/**/index.ts",
+        |file, crate_path| async move {
+            let cwd = file.parent().unwrap();
+            println!("testing minify: {:?}", cwd);
+
+            let entry_name = "index".to_string();
+            let compiler = create_compiler(
+                HashMap::from([(entry_name.clone(), "./index.ts".to_string())]),
+                cwd.to_path_buf(),
+                crate_path,
+                true,
+            )
+            .await;
+
+            compiler.compile().await.unwrap();
+
+            let resources_map = compiler.context().resources_map.lock().await;
+            let css = String::from_utf8(resources_map["index.css"].bytes.clone()).unwrap();
+            // the expected css is the section after `//index_xxx.css:` in output.js
+            let output = std::fs::read_to_string(cwd.join("output.js")).unwrap();
+            let expected = output.split(".css:\n").nth(1).unwrap().trim();
+
+            assert_eq!(css, expected);
+        }
+    );
+}
+
+#[tokio::test]
+async fn minify_html_test() {
+    fixture!(
+        "tests/fixtures/minify/html/**/index.html",
+        |file, crate_path| async move {
+            let cwd = file.parent().unwrap();
+            println!("testing minify: {:?}", cwd);
+
+            let entry_name = "index".to_string();
+            let compiler = create_compiler(
+                HashMap::from([(entry_name.clone(), "./index.html".to_string())]),
+                cwd.to_path_buf(),
+                crate_path,
+                true,
+            )
+            .await;
+
+            compiler.compile().await.unwrap();
+
+            let resources_map = compiler.context().resources_map.lock().await;
+            let html = String::from_utf8(resources_map["index.html"].bytes.clone()).unwrap();
+
+            assert!(
+                html.starts_with("<!doctype html><html lang=en>"),
+                "{}",
+                html
+            );
+            // local dependencies are replaced by the generated resources
+            assert!(!html.contains("./index."), "{}", html);
+
+            for resource in resources_map.values() {
+                if resource.resource_type == ResourceType::Css {
+                    assert!(
+                        html.contains(&format!("<link rel=stylesheet href=/{}>", resource.name)),
+                        "{}",
+                        html
+                    );
+                }
+            }
+
+            // the local script of the entry is bundled into the script resource of the entry
+            let source = std::fs::read_to_string(&file).unwrap();
+            let scripts = resources_map
+                .values()
+                .filter(|resource| resource.resource_type == ResourceType::Js)
+                .collect::<Vec<_>>();
+            assert_eq!(scripts.len(), usize::from(source.contains("<script")));
+
+            for script in scripts {
+                assert_eq!(script.entry.as_deref(), Some("index"));
+                // `</body></html>` are omitted by the minifier, so the script ends the document when injected at the end of <body>
+                assert!(
+                    html.ends_with(&format!("<script src=/{}></script>", script.name)),
+                    "{}",
+                    html
+                );
+            }
+        }
+    );
+}
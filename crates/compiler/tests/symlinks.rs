@@ -0,0 +1,90 @@
+#![cfg(unix)]
+
+use std::{collections::HashMap, path::PathBuf};
+
+use toy_farm_core::{ModuleId, UpdateType};
+
+mod common;
+use common::{create_compiler, create_dir, write};
+
+#[tokio::test]
+async fn dedupe_symlinked_modules() {
+    let dir = create_dir("toy_farm_dedupe_symlinked_modules");
+
+    write(
+        &dir,
+        "index.css",
+        "@import 'real/a.css';\n@import 'linked/a.css';",
+    );
+    write(&dir, "real/a.css", ".a { color: red; }");
+    std::os::unix::fs::symlink("real", dir.join("linked")).unwrap();
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    let module_graph = compiler.context().module_graph.read().await;
+    let mut module_ids = module_graph
+        .modules()
+        .into_iter()
+        .map(|module| module.id.to_string())
+        .collect::<Vec<_>>();
+    module_ids.sort();
+
+    assert_eq!(
+        module_ids,
+        vec![
+            dir.join("index.css").to_string_lossy().to_string(),
+            dir.join("real/a.css").to_string_lossy().to_string(),
+        ]
+    );
+    drop(module_graph);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn remove_symlinked_module() {
+    let dir = create_dir("toy_farm_remove_symlinked_module");
+
+    write(&dir, "index.css", "@import 'linked/a.css';");
+    write(&dir, "real/a.css", ".a { color: red; }");
+    std::os::unix::fs::symlink("real", dir.join("linked")).unwrap();
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    // the removed file can not be canonicalized, its directory is resolved to match the module id
+    write(&dir, "index.css", ".index { color: red; }");
+    std::fs::remove_file(dir.join("real/a.css")).unwrap();
+    let result = compiler
+        .update(vec![("linked/a.css".to_string(), UpdateType::Removed)])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.removed_module_ids,
+        vec![ModuleId::from(
+            dir.join("real/a.css").to_string_lossy().as_ref()
+        )]
+    );
+    assert_eq!(
+        result.updated_module_ids,
+        vec![ModuleId::from(
+            dir.join("index.css").to_string_lossy().as_ref()
+        )]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
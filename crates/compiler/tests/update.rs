@@ -0,0 +1,266 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use toy_farm_compiler::Compiler;
+use toy_farm_core::{
+    persistent_cache::PersistentCacheConfig, record::Trigger, CompilationError, Config, ModuleId,
+    UpdateType,
+};
+mod common;
+use common::{create_compiler, create_dir, write};
+
+#[tokio::test]
+async fn update_modules() {
+    let dir = create_dir("toy_farm_update_modules");
+
+    let index = write(
+        &dir,
+        "index.css",
+        "@import 'a.css';\n.index { color: red; }",
+    );
+    let a = write(&dir, "a.css", ".a { color: red; }");
+    let b = write(&dir, "b.css", ".b { color: red; }");
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    // a.css imports b.css now
+    write(&dir, "a.css", "@import 'b.css';\n.a { color: blue; }");
+    let result = compiler
+        .update(vec![(a.clone(), UpdateType::Updated)])
+        .await
+        .unwrap();
+
+    assert_eq!(result.updated_module_ids, vec![ModuleId::from(a.as_str())]);
+    assert_eq!(result.added_module_ids, vec![ModuleId::from(b.as_str())]);
+    assert!(result.removed_module_ids.is_empty());
+    {
+        let module_graph = compiler.context().module_graph.read().await;
+        assert!(module_graph.has_edge(&a.as_str().into(), &b.as_str().into()));
+        assert!(module_graph.has_edge(&index.as_str().into(), &a.as_str().into()));
+    }
+
+    let resources_map = compiler.context().resources_map.lock().await;
+    let css = String::from_utf8(resources_map["index.css"].bytes.clone()).unwrap();
+    assert!(css.contains("blue"), "{}", css);
+    drop(resources_map);
+
+    // b.css is no longer imported
+    write(&dir, "a.css", ".a { color: green; }");
+    let result = compiler
+        .update(vec![("a.css".to_string(), UpdateType::Updated)])
+        .await
+        .unwrap();
+
+    assert!(result.added_module_ids.is_empty());
+    assert_eq!(result.removed_module_ids, vec![ModuleId::from(b.as_str())]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn update_watched_tsconfig() {
+    let dir = create_dir("toy_farm_update_watched_tsconfig");
+    std::fs::create_dir_all(dir.join("v1")).unwrap();
+    std::fs::create_dir_all(dir.join("v2")).unwrap();
+
+    let index = write(&dir, "index.css", "@import '@styles/a.css';");
+    let v1 = write(&dir, "v1/a.css", ".a { color: red; }");
+    let v2 = write(&dir, "v2/a.css", ".a { color: blue; }");
+    write(
+        &dir,
+        "tsconfig.json",
+        r#"{ "compilerOptions": { "paths": { "@styles/*": ["v1/*"] } } }"#,
+    );
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    // tsconfig.json is not a module, the modules resolved through it are rebuilt
+    write(
+        &dir,
+        "tsconfig.json",
+        r#"{ "compilerOptions": { "paths": { "@styles/*": ["v2/*"] } } }"#,
+    );
+    let result = compiler
+        .update(vec![("tsconfig.json".to_string(), UpdateType::Updated)])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.updated_module_ids,
+        vec![ModuleId::from(index.as_str())]
+    );
+    assert_eq!(result.added_module_ids, vec![ModuleId::from(v2.as_str())]);
+    assert_eq!(result.removed_module_ids, vec![ModuleId::from(v1.as_str())]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn update_build_error() {
+    let dir = create_dir("toy_farm_update_build_error");
+
+    let index = write(&dir, "index.css", "@import 'a.css';");
+    let a = write(&dir, "a.css", "@import 'b.css';");
+    let b = write(&dir, "b.css", ".b { color: red; }");
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    // the module graph is kept as it is if the updated module fails to build
+    write(&dir, "a.css", "@import 'b.css';\n.a { color: }}");
+    let result = compiler
+        .update(vec![(a.clone(), UpdateType::Updated)])
+        .await;
+    assert!(
+        matches!(result, Err(CompilationError::ParseError { .. })),
+        "{:?}",
+        result
+    );
+    {
+        let module_graph = compiler.context().module_graph.read().await;
+        assert!(module_graph.has_edge(&index.as_str().into(), &a.as_str().into()));
+        assert!(module_graph.has_edge(&a.as_str().into(), &b.as_str().into()));
+    }
+
+    // the module graph is still updated if a dependency fails to resolve
+    write(&dir, "a.css", "@import 'missing.css';");
+    match compiler
+        .update(vec![(a.clone(), UpdateType::Updated)])
+        .await
+    {
+        Err(CompilationError::ResolveError { src, .. }) => assert_eq!(src, "missing.css"),
+        result => panic!("expected a resolve error, got {:?}", result),
+    }
+    {
+        let module_graph = compiler.context().module_graph.read().await;
+        assert!(module_graph.has_edge(&index.as_str().into(), &a.as_str().into()));
+        assert!(!module_graph.has_module(&b.as_str().into()));
+    }
+
+    write(&dir, "a.css", "@import 'b.css';");
+    let result = compiler
+        .update(vec![(a.clone(), UpdateType::Updated)])
+        .await
+        .unwrap();
+    assert_eq!(result.added_module_ids, vec![ModuleId::from(b.as_str())]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn update_removed_module() {
+    let dir = create_dir("toy_farm_update_removed_module");
+
+    let index = write(&dir, "index.css", "@import 'a.css';\n@import 'b.css';");
+    let a = write(&dir, "a.css", "@import 'c.css';\n.a { color: red; }");
+    let b = write(&dir, "b.css", ".b { color: red; }");
+    let c = write(&dir, "c.css", ".c { color: red; }");
+
+    let compiler = create_compiler(
+        HashMap::from([("index".to_string(), "index.css".to_string())]),
+        dir.clone(),
+        PathBuf::new(),
+        false,
+    )
+    .await;
+    compiler.compile().await.unwrap();
+
+    // the dependents of the removed module are rebuilt, and the dependencies only imported by it are removed
+    write(&dir, "index.css", "@import 'b.css';");
+    std::fs::remove_file(&a).unwrap();
+    let result = compiler
+        .update(vec![(a.clone(), UpdateType::Removed)])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.updated_module_ids,
+        vec![ModuleId::from(index.as_str())]
+    );
+    assert!(result.added_module_ids.is_empty());
+    assert_eq!(
+        result.removed_module_ids,
+        vec![ModuleId::from(a.as_str()), ModuleId::from(c.as_str())]
+    );
+    {
+        let module_graph = compiler.context().module_graph.read().await;
+        assert!(module_graph.has_edge(&index.as_str().into(), &b.as_str().into()));
+    }
+
+    let resources_map = compiler.context().resources_map.lock().await;
+    let css = String::from_utf8(resources_map["index.css"].bytes.clone()).unwrap();
+    assert!(!css.contains(".a") && !css.contains(".c"), "{}", css);
+
+    drop(resources_map);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn update_records_trigger() {
+    let dir = create_dir("toy_farm_update_records_trigger");
+
+    write(&dir, "index.css", "@import 'a.css';");
+    let a = write(&dir, "a.css", ".a { color: red; }");
+
+    let compiler = Compiler::new(Config {
+        input: HashMap::from([("index".to_string(), "index.css".to_string())]),
+        root: dir.to_string_lossy().to_string(),
+        persistent_cache: Box::new(PersistentCacheConfig::Bool(false)),
+        record: true,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    compiler.compile().await.unwrap();
+
+    write(&dir, "a.css", ".a { color: blue; }");
+    compiler
+        .update(vec![(a.clone(), UpdateType::Updated)])
+        .await
+        .unwrap();
+    // the trigger is reset after the update
+    compiler.generate().await.unwrap();
+
+    let record_manager = &compiler.context().record_manager;
+    let is_update = |trigger: &Trigger| matches!(trigger, Trigger::Update);
+
+    let analyze_deps_records = record_manager.get_analyze_deps_records_by_id(&a).await;
+    assert_eq!(
+        analyze_deps_records
+            .iter()
+            .map(|record| is_update(&record.trigger))
+            .collect::<Vec<_>>(),
+        vec![false, true]
+    );
+
+    // the resources regenerated by the update are tagged too
+    let resource_pot_records = record_manager.get_resource_pot_records_by_id("index").await;
+    assert_eq!(
+        resource_pot_records
+            .iter()
+            .map(|record| is_update(&record.trigger))
+            .collect::<Vec<_>>(),
+        vec![false, true, false]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -87,7 +87,7 @@ impl CacheStore {
         self.manifest.contains_key(name)
     }
 
-    pub fn get_store_keys(&self) -> Vec<RefMulti<String, String>> {
+    pub fn get_store_keys(&self) -> Vec<RefMulti<'_, String, String>> {
         self.manifest.iter().collect()
     }
 
@@ -131,6 +131,13 @@ impl CacheStore {
         Ok(())
     }
 
+    /// Remove the cache of `name` from the manifest and the disk.
+    pub async fn remove_cache(&self, name: &str) {
+        if let Some((_, cache_key)) = self.manifest.remove(name) {
+            fs::remove_file(self.cache_dir.join(cache_key)).await.ok();
+        }
+    }
+
     pub async fn write_manifest(&self) {
         let manifest = self.manifest.clone().into_iter().collect::<HashMap<_, _>>();
         if !self.cache_dir.exists() {
@@ -1,6 +1,7 @@
-use cache_store::CacheStore;
+use cache_store::{CacheStore, CacheStoreKey};
 use module_cache::ModuleCacheManager;
 use tokio::sync::Mutex;
+use toy_farm_utils::hash::sha256;
 
 use crate::Mode;
 
@@ -13,6 +14,7 @@ pub struct CacheManager {
 
     pub lazy_compile_store: CacheStore,
 
+    /// plugin caches, plugin name -> bytes written by the plugin
     pub custom: CacheStore,
 
     pub lock: Mutex<bool>,
@@ -32,8 +34,100 @@ impl CacheManager {
         let mut lock = self.lock.lock().await;
         *lock = true;
 
-        // TODO: write cache
-        tokio::join!(self.module_cache.write_cache(),);
+        tokio::join!(
+            self.module_cache.write_cache(),
+            self.custom.write_manifest()
+        );
         *lock = false;
     }
+
+    fn plugin_cache_store_key(plugin_name: &str, cache_key: &str) -> CacheStoreKey {
+        CacheStoreKey {
+            name: plugin_name.to_string(),
+            key: sha256(format!("{}{}", plugin_name, cache_key).as_bytes(), 32),
+        }
+    }
+
+    /// Read the cache written by the plugin in the previous build.
+    /// The cache is cleared and [None] is returned if the cache key of the plugin changed.
+    pub async fn read_plugin_cache(&self, plugin_name: &str, cache_key: &str) -> Option<Vec<u8>> {
+        let store_key = Self::plugin_cache_store_key(plugin_name, cache_key);
+
+        if !self.custom.has_cache(plugin_name) {
+            return None;
+        }
+
+        if self.custom.is_cache_changed(&store_key) {
+            self.custom.remove_cache(plugin_name).await;
+            return None;
+        }
+
+        self.custom.read_cache(plugin_name)
+    }
+
+    /// Write the cache of the plugin, it's persisted to the disk with the manifest in [CacheManager::write_cache].
+    pub async fn write_plugin_cache(
+        &self,
+        plugin_name: &str,
+        cache_key: &str,
+        bytes: Vec<u8>,
+    ) -> std::io::Result<()> {
+        // the store only writes changed cache keys, but the bytes may change with the same cache key
+        self.custom.remove_cache(plugin_name).await;
+        self.custom
+            .write_single_cache(Self::plugin_cache_store_key(plugin_name, cache_key), bytes)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CacheManager;
+    use crate::Mode;
+
+    #[tokio::test]
+    async fn plugin_cache() {
+        // the store is created next to `cache`, remove the whole dir so the test starts from a fresh store
+        let root = std::env::temp_dir().join("toy_farm_cache_manager_plugin_cache");
+        let _ = std::fs::remove_dir_all(&root);
+        let cache_dir = root.join("cache");
+        let cache_dir = cache_dir.to_string_lossy();
+        let cache_manager = CacheManager::new(&cache_dir, "plugin_cache", Mode::Development);
+
+        assert_eq!(
+            cache_manager.read_plugin_cache("plugin-a", "v1").await,
+            None
+        );
+
+        for cache in [b"first".to_vec(), b"second".to_vec()] {
+            cache_manager
+                .write_plugin_cache("plugin-a", "v1", cache)
+                .await
+                .unwrap();
+        }
+        cache_manager.write_cache().await;
+
+        // read the cache in the next build
+        let cache_manager = CacheManager::new(&cache_dir, "plugin_cache", Mode::Development);
+        assert_eq!(
+            cache_manager.read_plugin_cache("plugin-a", "v1").await,
+            Some(b"second".to_vec())
+        );
+        assert_eq!(
+            cache_manager.read_plugin_cache("plugin-b", "v1").await,
+            None
+        );
+
+        // the cache is cleared when the cache key changes
+        assert_eq!(
+            cache_manager.read_plugin_cache("plugin-a", "v2").await,
+            None
+        );
+        assert_eq!(
+            cache_manager.read_plugin_cache("plugin-a", "v1").await,
+            None
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -52,7 +52,7 @@ impl CachedModule {
                     .into_iter()
                     .map(|item| (item.source, item.kind, item.order))
                     .collect::<Vec<_>>();
-                sorted_dep.sort_by(|a, b| a.2.cmp(&b.2));
+                sorted_dep.sort_by_key(|a| a.2);
 
                 sorted_dep.into_iter().map(move |item| {
                     (
@@ -129,10 +129,9 @@ impl ModuleCacheManager {
             return module;
         }
 
-        return self
-            .immutable_modules_store
+        self.immutable_modules_store
             .get_cache_mut_ref(key)
-            .expect("Cache broken, please remove node_modules/.farm and retry.");
+            .expect("Cache broken, please remove node_modules/.farm and retry.")
     }
 
     pub async fn write_cache(&self) {
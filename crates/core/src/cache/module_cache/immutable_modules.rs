@@ -289,7 +289,7 @@ impl ModuleMemoryStore for ImmutableModulesMemoryStore {
                         })
                         .collect(),
                     name: package_key.split('@').next().unwrap().to_string(),
-                    version: package_key.split('@').last().unwrap().to_string(),
+                    version: package_key.split('@').next_back().unwrap().to_string(),
                 };
 
                 let package_bytes = crate::serialize!(&package);
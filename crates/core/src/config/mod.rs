@@ -4,6 +4,8 @@ use config_regex::ConfigRegex;
 use persistent_cache::PersistentCacheConfig;
 use serde::{Deserialize, Serialize};
 
+use crate::ModuleType;
+
 pub mod config_regex;
 pub mod custom;
 pub mod external;
@@ -19,6 +21,81 @@ pub struct Config {
     pub custom: Box<HashMap<String, String>>,
     pub external: Vec<ConfigRegex>,
     pub resolve: ResolveConfig,
+    pub minify: bool,
+    pub sourcemap: SourcemapConfig,
+    /// plugins loaded from shared libraries, see [crate::plugin::native_plugin]
+    pub native_plugins: Vec<NativePluginConfig>,
+    /// plugins running in child processes, see [crate::plugin::process_plugin]
+    pub process_plugins: Vec<ProcessPluginConfig>,
+    /// sandboxed plugins compiled to WebAssembly, see [crate::plugin::wasm_plugin]
+    pub wasm_plugins: Vec<WasmPluginConfig>,
+    /// virtual modules registered before the compilation, id -> module, see [crate::VirtualModules]
+    pub virtual_modules: HashMap<String, VirtualModuleConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VirtualModuleConfig {
+    pub content: String,
+    pub module_type: ModuleType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NativePluginConfig {
+    /// path of the shared library, relative to the root
+    pub path: String,
+    /// options passed to the constructor of the plugin, usually a json string
+    pub options: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProcessPluginConfig {
+    /// the executable of the plugin
+    pub command: String,
+    pub args: Vec<String>,
+    /// timeout of each request in milliseconds, the child is killed if it does not reply in time
+    pub timeout: u64,
+}
+
+impl Default for ProcessPluginConfig {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            args: vec![],
+            timeout: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SourcemapConfig {
+    /// collapse the source map chain of each module into one source map after transform,
+    /// which maps the transformed content to the original source directly.
+    pub collapse: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WasmPluginConfig {
+    /// path of the wasm module, relative to the root
+    pub path: String,
+    /// max size of the linear memory in bytes
+    pub memory_limit: usize,
+    /// fuel of each hook call, roughly the number of executed instructions
+    pub fuel: u64,
+}
+
+impl Default for WasmPluginConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            memory_limit: 64 << 20,
+            fuel: 1_000_000_000,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -92,12 +169,16 @@ impl Default for Config {
             // css: Default::default(),
             // html: Box::default(),
             // assets: Default::default(),
-            // sourcemap: Default::default(),
+            sourcemap: Default::default(),
+            native_plugins: vec![],
+            process_plugins: vec![],
+            wasm_plugins: vec![],
+            virtual_modules: HashMap::new(),
             // partial_bundling: PartialBundlingConfig::default(),
             // lazy_compilation: true,
             // core_lib_path: None,
             // tree_shaking: true,
-            // minify: Box::new(BoolOrObj::Bool(true)),
+            minify: false,
             // preset_env: Box::<PresetEnvConfig>::default(),
             record: false,
             // progress: true,
@@ -129,6 +210,22 @@ pub struct OutputConfig {
     //   pub format: ModuleFormat,
 }
 
+impl OutputConfig {
+    /// render the file name of a resource using [OutputConfig::filename], e.g. `[resourceName].[ext]` -> `index.css`
+    pub fn resource_filename(&self, resource_name: &str, ext: &str) -> String {
+        self.filename
+            .replace("[resourceName]", resource_name)
+            .replace("[ext]", ext)
+    }
+
+    /// render the file name of an entry resource using [OutputConfig::entry_filename], e.g. `[entryName].[ext]` -> `index.html`
+    pub fn entry_resource_filename(&self, entry_name: &str, ext: &str) -> String {
+        self.entry_filename
+            .replace("[entryName]", entry_name)
+            .replace("[ext]", ext)
+    }
+}
+
 impl Default for OutputConfig {
     fn default() -> Self {
         Self {
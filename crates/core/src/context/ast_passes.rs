@@ -0,0 +1,300 @@
+use std::sync::{Arc, RwLock};
+
+use swc_common::{comments::SingleThreadedComments, Globals, Mark, GLOBALS};
+
+use crate::{ModuleId, ModuleMetaData, ModuleType, PluginProcessModuleHookParam, DEFAULT_PRIORITY};
+
+/// Parameter passed to [AstPass::script_visitor].
+pub struct ScriptAstPassParam<'a> {
+    pub module_id: &'a ModuleId,
+    pub module_type: &'a ModuleType,
+    pub top_level_mark: Mark,
+    pub unresolved_mark: Mark,
+    /// comments of the module shared by all passes, comments added by a pass are visible to the following passes
+    pub comments: &'a SingleThreadedComments,
+}
+
+/// Parameter passed to [AstPass::css_visitor].
+pub struct CssAstPassParam<'a> {
+    pub module_id: &'a ModuleId,
+    pub module_type: &'a ModuleType,
+    /// comments of the module shared by all passes
+    pub comments: &'a SingleThreadedComments,
+}
+
+/// Parameter passed to [AstPass::html_visitor].
+pub struct HtmlAstPassParam<'a> {
+    pub module_id: &'a ModuleId,
+    pub module_type: &'a ModuleType,
+}
+
+/// A syntax transformation applied to the parsed ast of modules after the `process_module` hook,
+/// so plugins can edit the ast without re-parsing the content in `transform`.
+/// Return [None] from the visitor factories to skip the module.
+pub trait AstPass: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Passes with higher priority run first, passes with the same priority run in the registered order.
+    fn priority(&self) -> i32 {
+        DEFAULT_PRIORITY
+    }
+
+    /// Called under the swc globals of the compilation, so the marks can be used to create new syntax contexts.
+    fn script_visitor<'a>(
+        &'a self,
+        _param: &ScriptAstPassParam<'a>,
+    ) -> Option<Box<dyn swc_ecma_visit::VisitMut + 'a>> {
+        None
+    }
+
+    fn css_visitor<'a>(
+        &'a self,
+        _param: &CssAstPassParam<'a>,
+    ) -> Option<Box<dyn swc_css_visit::VisitMut + 'a>> {
+        None
+    }
+
+    fn html_visitor<'a>(
+        &'a self,
+        _param: &HtmlAstPassParam<'a>,
+    ) -> Option<Box<dyn swc_html_visit::VisitMut + 'a>> {
+        None
+    }
+}
+
+/// Ast passes registered by plugins, usually in `build_start`. All passes run over the same parsed ast
+/// of a module, the meta data of [ModuleMetaData::Custom] is left to the `process_module` hook.
+#[derive(Default)]
+pub struct AstPasses {
+    passes: RwLock<Vec<Arc<dyn AstPass>>>,
+}
+
+impl AstPasses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a pass ordered by [AstPass::priority].
+    /// Return false if a pass with the same name is already registered.
+    pub fn register(&self, pass: Arc<dyn AstPass>) -> bool {
+        let mut passes = self.passes.write().unwrap();
+
+        if passes.iter().any(|p| p.name() == pass.name()) {
+            return false;
+        }
+
+        let index = passes
+            .iter()
+            .position(|p| p.priority() < pass.priority())
+            .unwrap_or(passes.len());
+        passes.insert(index, pass);
+
+        true
+    }
+
+    /// names of the registered passes in the order they run
+    pub fn names(&self) -> Vec<String> {
+        self.passes
+            .read()
+            .unwrap()
+            .iter()
+            .map(|pass| pass.name().to_string())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.read().unwrap().is_empty()
+    }
+
+    /// Run all registered passes over the meta data of the module in order.
+    pub fn apply(&self, param: &mut PluginProcessModuleHookParam, globals: &Globals) {
+        let passes = self.passes.read().unwrap().clone();
+
+        if passes.is_empty() {
+            return;
+        }
+
+        match param.meta {
+            ModuleMetaData::Script(script) => GLOBALS.set(globals, || {
+                let comments = SingleThreadedComments::from(std::mem::take(&mut script.comments));
+                let pass_param = ScriptAstPassParam {
+                    module_id: param.module_id,
+                    module_type: param.module_type,
+                    top_level_mark: Mark::from_u32(script.top_level_mark),
+                    unresolved_mark: Mark::from_u32(script.unresolved_mark),
+                    comments: &comments,
+                };
+
+                for pass in &passes {
+                    if let Some(mut visitor) = pass.script_visitor(&pass_param) {
+                        visitor.visit_mut_module(&mut script.ast);
+                    }
+                }
+
+                script.comments = comments.into();
+            }),
+            ModuleMetaData::Css(css) => {
+                let comments = SingleThreadedComments::from(std::mem::take(&mut css.comments));
+                let pass_param = CssAstPassParam {
+                    module_id: param.module_id,
+                    module_type: param.module_type,
+                    comments: &comments,
+                };
+
+                for pass in &passes {
+                    if let Some(mut visitor) = pass.css_visitor(&pass_param) {
+                        visitor.visit_mut_stylesheet(&mut css.ast);
+                    }
+                }
+
+                css.comments = comments.into();
+            }
+            ModuleMetaData::Html(html) => {
+                let pass_param = HtmlAstPassParam {
+                    module_id: param.module_id,
+                    module_type: param.module_type,
+                };
+
+                for pass in &passes {
+                    if let Some(mut visitor) = pass.html_visitor(&pass_param) {
+                        visitor.visit_mut_document(&mut html.ast);
+                    }
+                }
+            }
+            ModuleMetaData::Custom(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_common::{
+        comments::{Comment, CommentKind, Comments},
+        BytePos, Globals, DUMMY_SP,
+    };
+    use swc_ecma_ast::{Ident, Module as SwcModule};
+    use swc_ecma_visit::VisitMut;
+
+    use crate::{
+        ModuleId, ModuleMetaData, ModuleType, PluginProcessModuleHookParam, ScriptModuleMetaData,
+    };
+
+    use super::{AstPass, AstPasses, ScriptAstPassParam};
+
+    struct RenamePass {
+        name: &'static str,
+        priority: i32,
+    }
+
+    struct RenameVisitor<'a> {
+        suffix: &'a str,
+        comments: &'a swc_common::comments::SingleThreadedComments,
+    }
+
+    impl VisitMut for RenameVisitor<'_> {
+        fn visit_mut_ident(&mut self, ident: &mut Ident) {
+            ident.sym = format!("{}_{}", ident.sym, self.suffix).into();
+        }
+
+        fn visit_mut_module(&mut self, module: &mut SwcModule) {
+            self.comments.add_leading(
+                BytePos(1),
+                Comment {
+                    kind: CommentKind::Line,
+                    span: DUMMY_SP,
+                    text: self.suffix.into(),
+                },
+            );
+            swc_ecma_visit::visit_mut_module(self, module);
+        }
+    }
+
+    impl AstPass for RenamePass {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+
+        fn script_visitor<'a>(
+            &'a self,
+            param: &ScriptAstPassParam<'a>,
+        ) -> Option<Box<dyn VisitMut + 'a>> {
+            Some(Box::new(RenameVisitor {
+                suffix: self.name,
+                comments: param.comments,
+            }))
+        }
+    }
+
+    #[test]
+    fn apply_in_order() {
+        let ast_passes = AstPasses::new();
+
+        assert!(ast_passes.register(Arc::new(RenamePass {
+            name: "a",
+            priority: 100,
+        })));
+        assert!(ast_passes.register(Arc::new(RenamePass {
+            name: "b",
+            priority: 200,
+        })));
+        assert!(ast_passes.register(Arc::new(RenamePass {
+            name: "c",
+            priority: 100,
+        })));
+        assert!(!ast_passes.register(Arc::new(RenamePass {
+            name: "a",
+            priority: 300,
+        })));
+        assert_eq!(ast_passes.names(), vec!["b", "a", "c"]);
+
+        let globals = Globals::new();
+        let mut meta = ModuleMetaData::Script(ScriptModuleMetaData {
+            ast: swc_ecma_parser::parse_file_as_module(
+                &swc_common::SourceFile::new(
+                    swc_common::FileName::Anon,
+                    false,
+                    swc_common::FileName::Anon,
+                    "foo;".to_string(),
+                    BytePos(1),
+                ),
+                Default::default(),
+                Default::default(),
+                None,
+                &mut vec![],
+            )
+            .unwrap(),
+            ..Default::default()
+        });
+
+        ast_passes.apply(
+            &mut PluginProcessModuleHookParam {
+                module_id: &ModuleId::from("index.js"),
+                module_type: &ModuleType::Js,
+                content: Arc::new("foo;".to_string()),
+                meta: &mut meta,
+            },
+            &globals,
+        );
+
+        let script = meta.as_script();
+        let ident = script.ast.body[0]
+            .as_stmt()
+            .and_then(|stmt| stmt.as_expr())
+            .and_then(|expr| expr.expr.as_ident())
+            .unwrap();
+        assert_eq!(&*ident.sym, "foo_b_a_c");
+
+        let comments = script.comments.leading[0]
+            .comment
+            .iter()
+            .map(|comment| comment.text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec!["b", "a", "c"]);
+    }
+}
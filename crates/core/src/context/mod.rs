@@ -1,13 +1,21 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use tokio::sync::RwLock;
+use swc_common::Globals;
+use tokio::sync::{Mutex, RwLock};
+
+mod ast_passes;
+mod virtual_modules;
+
+pub use ast_passes::*;
+pub use virtual_modules::*;
 
 use crate::{
+    error::Result,
     persistent_cache::PersistentCacheConfig,
     plugin_driver::PluginDriver,
     record::{ModuleRecord, RecordManager},
     watch_graph::WatchGraph,
-    CacheManager, Config, ModuleGraph, Plugin,
+    CacheManager, Config, ModuleGraph, Plugin, Resource,
 };
 
 pub struct CompilationContext {
@@ -17,26 +25,54 @@ pub struct CompilationContext {
     pub watch_graph: Box<RwLock<WatchGraph>>,
     pub record_manager: Box<RecordManager>,
     pub plugin_driver: Box<PluginDriver>,
+    pub meta: Box<ContextMetaData>,
+    /// resources generated by the generate stage, resource name -> resource
+    pub resources_map: Box<Mutex<HashMap<String, Resource>>>,
+    pub virtual_modules: Box<VirtualModules>,
+    /// ast passes registered by plugins, applied after the `process_module` hook
+    pub ast_passes: Box<AstPasses>,
+}
+
+/// Shared meta data of the compilation, for example, the swc globals used by script plugins
+#[derive(Default)]
+pub struct ContextMetaData {
+    pub script: ScriptContextMetaData,
+}
+
+#[derive(Default)]
+pub struct ScriptContextMetaData {
+    /// swc globals, [swc_common::Mark]s like `top_level_mark` and `unresolved_mark` of script modules are created under it
+    pub globals: Globals,
 }
 
 pub(crate) const EMPTY_STR: &str = "";
 
 impl CompilationContext {
-    pub fn new(mut config: Config, plugins: Vec<Arc<dyn Plugin>>) -> CompilationContext {
+    pub fn new(mut config: Config, plugins: Vec<Arc<dyn Plugin>>) -> Result<CompilationContext> {
         let (cache_dir, namespace) =
             CompilationContext::normalize_persistent_cache_config(&mut config);
-        CompilationContext {
+        let virtual_modules = VirtualModules::new();
+
+        for (id, module) in &config.virtual_modules {
+            virtual_modules.register(id, module.content.clone(), module.module_type.clone());
+        }
+
+        Ok(CompilationContext {
             module_graph: Box::new(RwLock::new(ModuleGraph::new())),
             cache_manager: Box::new(CacheManager::new(
                 &cache_dir,
                 &namespace,
                 config.mode.clone(),
             )),
-            plugin_driver: Box::new(PluginDriver::new(plugins, config.record)),
+            plugin_driver: Box::new(PluginDriver::new(plugins, config.record)?),
             config: Box::new(config),
             watch_graph: Box::new(RwLock::new(WatchGraph::new())),
             record_manager: Box::new(RecordManager::new()),
-        }
+            meta: Box::default(),
+            resources_map: Box::new(Mutex::new(HashMap::new())),
+            virtual_modules: Box::new(virtual_modules),
+            ast_passes: Box::default(),
+        })
     }
 
     pub fn normalize_persistent_cache_config(config: &mut Config) -> (String, String) {
@@ -46,7 +82,7 @@ impl CompilationContext {
                 cache_config_obj.cache_dir.clone(),
                 cache_config_obj.namespace.clone(),
             );
-            config.persistent_cache = Box::new(PersistentCacheConfig::Obj(cache_config_obj));
+            *config.persistent_cache = PersistentCacheConfig::Obj(cache_config_obj);
 
             (cache_dir, namespace)
         } else {
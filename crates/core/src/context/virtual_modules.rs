@@ -0,0 +1,133 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use dashmap::DashMap;
+
+use crate::{ModuleType, VIRTUAL_MODULE_PREFIX};
+
+#[derive(Debug, Clone)]
+pub struct VirtualModule {
+    pub content: Arc<String>,
+    pub module_type: ModuleType,
+    /// the time this module is registered or updated in nanoseconds, used as the timestamp of the module,
+    /// so the cached module is invalidated when the content is updated
+    pub updated_at: u128,
+}
+
+/// Modules that do not exist on the disk, registered by plugins or `Config.virtual_modules`.
+/// The id of a virtual module always starts with [VIRTUAL_MODULE_PREFIX], e.g. `virtual:routes`,
+/// and it is resolved and loaded as is.
+#[derive(Default)]
+pub struct VirtualModules {
+    modules: DashMap<String, VirtualModule>,
+}
+
+impl VirtualModules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// prepend [VIRTUAL_MODULE_PREFIX] to the id if it does not start with it
+    pub fn normalize_id(id: &str) -> String {
+        if id.starts_with(VIRTUAL_MODULE_PREFIX) {
+            id.to_string()
+        } else {
+            format!("{}{}", VIRTUAL_MODULE_PREFIX, id)
+        }
+    }
+
+    /// Register a virtual module, or update it if the id is already registered.
+    /// Return false if the module is registered with the same content and module type.
+    pub fn register(&self, id: &str, content: impl Into<String>, module_type: ModuleType) -> bool {
+        let id = Self::normalize_id(id);
+        let content = content.into();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+
+        if let Some(mut module) = self.modules.get_mut(&id) {
+            if *module.content == content && module.module_type == module_type {
+                return false;
+            }
+
+            // the timestamp must change even if the module is updated in the same nanosecond
+            module.updated_at = now.max(module.updated_at + 1);
+            module.content = Arc::new(content);
+            module.module_type = module_type;
+        } else {
+            self.modules.insert(
+                id,
+                VirtualModule {
+                    content: Arc::new(content),
+                    module_type,
+                    updated_at: now,
+                },
+            );
+        }
+
+        true
+    }
+
+    pub fn get(&self, id: &str) -> Option<VirtualModule> {
+        self.modules
+            .get(&Self::normalize_id(id))
+            .map(|module| module.value().clone())
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.modules.contains_key(&Self::normalize_id(id))
+    }
+
+    pub fn remove(&self, id: &str) -> Option<VirtualModule> {
+        self.modules
+            .remove(&Self::normalize_id(id))
+            .map(|(_, module)| module)
+    }
+
+    /// ids of all registered virtual modules, sorted
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids = self
+            .modules
+            .iter()
+            .map(|module| module.key().clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ModuleType;
+
+    use super::VirtualModules;
+
+    #[test]
+    fn register_and_update() {
+        let virtual_modules = VirtualModules::new();
+
+        assert!(virtual_modules.register("routes", "export default []", ModuleType::Js));
+        assert!(virtual_modules.contains("virtual:routes"));
+        let registered = virtual_modules.get("routes").unwrap();
+
+        // registering the same content is a no-op
+        assert!(!virtual_modules.register("virtual:routes", "export default []", ModuleType::Js));
+        assert_eq!(
+            virtual_modules.get("routes").unwrap().updated_at,
+            registered.updated_at
+        );
+
+        assert!(virtual_modules.register("routes", "export default ['/']", ModuleType::Js));
+        let updated = virtual_modules.get("routes").unwrap();
+        assert_eq!(*updated.content, "export default ['/']");
+        assert!(updated.updated_at > registered.updated_at);
+
+        assert_eq!(virtual_modules.ids(), vec!["virtual:routes".to_string()]);
+        assert!(virtual_modules.remove("routes").is_some());
+        assert!(!virtual_modules.contains("routes"));
+    }
+}
@@ -36,6 +36,13 @@ pub enum CompilationError {
         #[source]
         source: Option<Box<dyn Error + Send + Sync>>,
     },
+
+    #[error("Hook `analyze_deps` execute failed for module `{resolved_path}`.\nOriginal error: {source:?}.")]
+    AnalyzeDepsError {
+        resolved_path: String,
+        #[source]
+        source: Option<Box<dyn Error + Send + Sync>>,
+    },
 }
 
 pub type Result<T> = core::result::Result<T, CompilationError>;
@@ -4,6 +4,7 @@ pub mod context;
 pub mod error;
 pub mod module;
 pub mod plugin;
+pub mod resource;
 
 pub use cache::*;
 pub use config::*;
@@ -12,6 +13,7 @@ pub use error::*;
 pub use module::*;
 pub use plugin::*;
 pub use relative_path;
+pub use resource::*;
 pub use rkyv;
 pub use rkyv_dyn;
 pub use rkyv_typename;
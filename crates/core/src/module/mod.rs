@@ -16,7 +16,10 @@ use relative_path::RelativePath;
 use rkyv::Deserialize;
 use rkyv_dyn::archive_dyn;
 use rkyv_typename::TypeName;
-use swc_common::{comments::Comment, BytePos, DUMMY_SP};
+use swc_common::{
+    comments::{Comment, SingleThreadedComments},
+    BytePos, DUMMY_SP,
+};
 use swc_css_ast::Stylesheet;
 use swc_ecma_ast::Module as SwcModule;
 use swc_html_ast::Document;
@@ -294,6 +297,46 @@ pub struct CommentsMetaData {
     pub leading: Vec<CommentsMetaDataItem>,
     pub trailing: Vec<CommentsMetaDataItem>,
 }
+
+impl From<SingleThreadedComments> for CommentsMetaData {
+    fn from(value: SingleThreadedComments) -> Self {
+        let (swc_leading_map, swc_trailing_map) = value.take_all();
+        let transform_comment_map = |map: swc_common::comments::SingleThreadedCommentsMapInner| {
+            let mut items = map
+                .into_iter()
+                .map(|(byte_pos, comment)| CommentsMetaDataItem { byte_pos, comment })
+                .collect::<Vec<CommentsMetaDataItem>>();
+            // make sure the order is stable
+            items.sort_by_key(|item| item.byte_pos);
+            items
+        };
+
+        let leading = transform_comment_map(swc_leading_map.take());
+        let trailing = transform_comment_map(swc_trailing_map.take());
+
+        Self { leading, trailing }
+    }
+}
+
+impl From<CommentsMetaData> for SingleThreadedComments {
+    fn from(value: CommentsMetaData) -> Self {
+        let leading = value
+            .leading
+            .into_iter()
+            .map(|item| (item.byte_pos, item.comment))
+            .collect();
+        let trailing = value
+            .trailing
+            .into_iter()
+            .map(|item| (item.byte_pos, item.comment))
+            .collect();
+
+        SingleThreadedComments::from_leading_and_trailing(
+            std::rc::Rc::new(std::cell::RefCell::new(leading)),
+            std::rc::Rc::new(std::cell::RefCell::new(trailing)),
+        )
+    }
+}
 impl ScriptModuleMetaData {
     pub fn take_ast(&mut self) -> SwcModule {
         std::mem::replace(
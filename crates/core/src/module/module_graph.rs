@@ -281,6 +281,144 @@ impl ModuleGraph {
         self.id_index_map.insert(id, index);
     }
 
+    /// ids of the modules created from the file, e.g. src/index.scss -> [src/index.scss, src/index.scss?raw]
+    pub fn module_ids_by_file(&self, file: &ModuleId) -> Vec<ModuleId> {
+        let mut module_ids = vec![];
+
+        if self.has_module(file) {
+            module_ids.push(file.clone());
+        }
+
+        if let Some(ids) = self.file_module_ids_map.get(file) {
+            module_ids.extend(ids.iter().cloned());
+        }
+
+        module_ids
+    }
+
+    pub fn module(&self, module_id: &ModuleId) -> Option<&Module> {
+        let i = self.id_index_map.get(module_id)?;
+        self.g.node_weight(*i)
+    }
+
+    pub fn module_mut(&mut self, module_id: &ModuleId) -> Option<&mut Module> {
+        let i = self.id_index_map.get(module_id)?;
+        self.g.node_weight_mut(*i)
+    }
+
+    pub fn modules(&self) -> Vec<&Module> {
+        self.g.node_weights().collect()
+    }
+
+    /// update [Module::execution_order] of all modules, dependencies are executed before the dependents,
+    /// for example, for `A -> B -> C`, the execution order is `C: 0, B: 1, A: 2`.
+    pub fn update_execution_order_for_modules(&mut self) {
+        let (mut topo_sorted_modules, _) = self.topo_sort();
+        topo_sorted_modules.reverse();
+
+        for (order, module_id) in topo_sorted_modules.iter().enumerate() {
+            if let Some(module) = self.module_mut(module_id) {
+                module.execution_order = order;
+            }
+        }
+    }
+
+    /// get all modules that statically reachable from the entry(dynamic imports are not included), including the entry itself,
+    /// sorted by [Module::execution_order].
+    pub fn static_reachable_modules(&self, entry: &ModuleId) -> Vec<ModuleId> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![entry.clone()];
+
+        while let Some(module_id) = stack.pop() {
+            if !visited.insert(module_id.clone()) {
+                continue;
+            }
+
+            for (dep, edge) in self.dependencies(&module_id) {
+                if !edge.is_dynamic() && !visited.contains(&dep) {
+                    stack.push(dep);
+                }
+            }
+        }
+
+        let mut modules = visited.into_iter().collect::<Vec<_>>();
+        modules.sort_by_key(|module_id| {
+            self.module(module_id)
+                .map(|module| module.execution_order)
+                .unwrap_or(usize::MAX)
+        });
+        modules
+    }
+
+    /// get dependents of the specific module, e.g. for `A -> C` and `B -> C`, the dependents of `C` are `[A, B]`
+    pub fn dependents(&self, module_id: &ModuleId) -> Vec<(ModuleId, &ModuleGraphEdge)> {
+        let i = self
+            .id_index_map
+            .get(module_id)
+            .unwrap_or_else(|| panic!("module_id {:?} should in the module graph", module_id));
+        let mut edges = self
+            .g
+            .neighbors_directed(*i, EdgeDirection::Incoming)
+            .detach();
+
+        let mut dependents = vec![];
+
+        while let Some((edge_index, node_index)) = edges.next(&self.g) {
+            dependents.push((self.g[node_index].id.clone(), &self.g[edge_index]));
+        }
+
+        dependents
+    }
+
+    pub fn edge(&self, from: &ModuleId, to: &ModuleId) -> Option<&ModuleGraphEdge> {
+        let from_index = self.id_index_map.get(from)?;
+        let to_index = self.id_index_map.get(to)?;
+        let edge_index = self.g.find_edge(*from_index, *to_index)?;
+
+        self.g.edge_weight(edge_index)
+    }
+
+    pub fn has_edge(&self, from: &ModuleId, to: &ModuleId) -> bool {
+        self.edge(from, to).is_some()
+    }
+
+    /// remove the edge between `from` and `to`, return the removed edge, [None] if the edge does not exist
+    pub fn remove_edge(&mut self, from: &ModuleId, to: &ModuleId) -> Option<ModuleGraphEdge> {
+        let from_index = self.id_index_map.get(from)?;
+        let to_index = self.id_index_map.get(to)?;
+        let edge_index = self.g.find_edge(*from_index, *to_index)?;
+
+        self.g.remove_edge(edge_index)
+    }
+
+    /// remove the module and all edges related to it from the graph, the module is also removed from [ModuleGraph::entries].
+    ///
+    /// Panic if the module does not exist
+    pub fn remove_module(&mut self, module_id: &ModuleId) -> Module {
+        let index = self
+            .id_index_map
+            .remove(module_id)
+            .unwrap_or_else(|| panic!("module_id {:?} should in the module graph", module_id));
+
+        if !module_id.query_string().is_empty() {
+            let rel_path: ModuleId = module_id.relative_path().into();
+
+            if let Some(module_ids) = self.file_module_ids_map.get_mut(&rel_path) {
+                module_ids.retain(|id| id != module_id);
+
+                if module_ids.is_empty() {
+                    self.file_module_ids_map.remove(&rel_path);
+                }
+            }
+        }
+
+        self.entries.remove(module_id);
+
+        self.g
+            .remove_node(index)
+            .expect("module should exist in the internal graph")
+    }
+
     pub fn replace_module(&mut self, module: Module) {
         let i = self
             .id_index_map
@@ -292,7 +430,7 @@ impl ModuleGraph {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
     use crate::{
         module::{Module, ModuleId},
@@ -388,6 +526,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn static_reachable_modules() {
+        let mut graph = construct_test_module_graph();
+        graph.update_execution_order_for_modules();
+
+        assert_eq!(
+            graph.static_reachable_modules(&"A".into()),
+            vec!["C".into(), "A".into()]
+        );
+        assert_eq!(
+            graph.static_reachable_modules(&"B".into()),
+            vec!["D".into(), "E".into(), "B".into()]
+        );
+    }
+
+    #[test]
+    fn mutate_module_graph() {
+        let mut graph = construct_test_module_graph();
+
+        assert_eq!(
+            graph
+                .dependents(&"D".into())
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<HashSet<_>>(),
+            HashSet::from(["A".into(), "B".into()])
+        );
+
+        // rewrite `A -> C` to `A -> E`
+        let edge = graph.remove_edge(&"A".into(), &"C".into()).unwrap();
+        assert!(!graph.has_edge(&"A".into(), &"C".into()));
+        assert!(graph.remove_edge(&"A".into(), &"C".into()).is_none());
+
+        for item in edge.items() {
+            graph
+                .add_edge_item(&"A".into(), &"E".into(), item.clone())
+                .unwrap();
+        }
+        assert_eq!(graph.edge(&"A".into(), &"E".into()), Some(&edge));
+
+        // C is not reachable from any entry
+        let removed = graph.remove_module(&"C".into());
+        assert_eq!(removed.id, "C".into());
+        assert!(!graph.has_module(&"C".into()));
+        assert_eq!(graph.dependents(&"F".into()).len(), 1);
+
+        let removed = graph.remove_module(&"A".into());
+        assert_eq!(removed.id, "A".into());
+        assert!(!graph.entries.contains_key(&"A".into()));
+        assert_eq!(graph.modules().len(), 5);
+    }
+
     #[test]
     fn dependencies() {
         let graph = construct_test_module_graph();
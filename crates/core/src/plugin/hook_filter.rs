@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+use crate::{config_regex::ConfigRegex, ModuleType, ResolveKind};
+
+/// Filter of a plugin hook, [crate::plugin_driver::PluginDriver] skips the plugin without calling the hook if the filter does not match.
+/// Empty fields match everything. Fields that the hook can not know are ignored, for example,
+/// the resolved path is unknown in the `resolve` hook so `include` and `exclude` do not apply to it.
+#[derive(Debug, Clone, Default)]
+pub struct PluginHookFilter {
+    /// the hook is called only if the resolved path matches any of the regexes
+    pub include: Vec<ConfigRegex>,
+    /// the hook is skipped if the resolved path matches any of the regexes, takes precedence over `include`
+    pub exclude: Vec<ConfigRegex>,
+    /// the hook is called only for these module types
+    pub module_types: HashSet<ModuleType>,
+    /// the hook is called only for these resolve kinds
+    pub resolve_kinds: HashSet<ResolveKind>,
+}
+
+impl PluginHookFilter {
+    pub fn matches(
+        &self,
+        resolved_path: Option<&str>,
+        module_type: Option<&ModuleType>,
+        kind: Option<&ResolveKind>,
+    ) -> bool {
+        if let Some(resolved_path) = resolved_path {
+            if self.exclude.iter().any(|r| r.is_match(resolved_path)) {
+                return false;
+            }
+
+            if !self.include.is_empty() && !self.include.iter().any(|r| r.is_match(resolved_path)) {
+                return false;
+            }
+        }
+
+        if let Some(module_type) = module_type {
+            if !self.module_types.is_empty() && !self.module_types.contains(module_type) {
+                return false;
+            }
+        }
+
+        if let Some(kind) = kind {
+            if !self.resolve_kinds.is_empty() && !self.resolve_kinds.contains(kind) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Filters of the per module hooks of a plugin, see [crate::Plugin::hook_filters]
+#[derive(Debug, Clone, Default)]
+pub struct PluginHookFilters {
+    /// only `resolve_kinds` applies
+    pub resolve: PluginHookFilter,
+    /// `module_types` does not apply as the module type is determined by load
+    pub load: PluginHookFilter,
+    pub transform: PluginHookFilter,
+    pub parse: PluginHookFilter,
+    pub process_module: PluginHookFilter,
+    pub analyze_deps: PluginHookFilter,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{config_regex::ConfigRegex, ModuleType, ResolveKind};
+
+    use super::PluginHookFilter;
+
+    #[test]
+    fn matches() {
+        let filter = PluginHookFilter {
+            include: vec![ConfigRegex::new("\\.css$")],
+            exclude: vec![ConfigRegex::new("node_modules/")],
+            module_types: HashSet::from([ModuleType::Css]),
+            resolve_kinds: HashSet::from([ResolveKind::CssAtImport]),
+        };
+
+        assert!(filter.matches(Some("/root/index.css"), Some(&ModuleType::Css), None));
+        assert!(!filter.matches(Some("/root/index.ts"), None, None));
+        assert!(!filter.matches(Some("/root/node_modules/a/index.css"), None, None));
+        assert!(!filter.matches(None, Some(&ModuleType::Js), None));
+        assert!(filter.matches(None, None, Some(&ResolveKind::CssAtImport)));
+        assert!(!filter.matches(None, None, Some(&ResolveKind::Import)));
+
+        // empty filter matches everything
+        assert!(PluginHookFilter::default().matches(
+            Some("/root/index.ts"),
+            Some(&ModuleType::Ts),
+            Some(&ResolveKind::Import)
+        ));
+    }
+}
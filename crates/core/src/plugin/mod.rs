@@ -4,9 +4,19 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use toy_farm_macro_cache_item::cache_item;
 
+mod hook_filter;
+pub mod native_plugin;
 pub mod plugin_driver;
+mod plugin_order;
+pub mod process_plugin;
+pub mod wasm_plugin;
 
-use crate::{error::Result, CompilationContext, Config, ModuleId, ModuleMetaData, ModuleType};
+pub use hook_filter::*;
+
+use crate::{
+    error::Result, CompilationContext, Config, Module, ModuleGraph, ModuleId, ModuleMetaData,
+    ModuleType, Resource,
+};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[cache_item]
@@ -56,6 +66,26 @@ pub struct PluginHookContext {
     pub meta: HashMap<String, String>,
 }
 
+impl PluginHookContext {
+    /// Whether the hook is called by the plugin named `plugin_name`, the plugin driver skips the caller
+    /// so a plugin can call the hook of other plugins without calling itself recursively.
+    pub fn is_caller(&self, plugin_name: &str) -> bool {
+        self.caller.as_deref() == Some(plugin_name)
+    }
+}
+
+// MARK: - config
+/// A modification of the config returned by the `config` hook.
+pub type ConfigPatch = Box<dyn FnOnce(&mut Config) + Send + Sync>;
+
+#[derive(Default)]
+pub struct PluginConfigHookResult {
+    /// plugins added by this plugin, e.g. the sub plugins of a framework plugin
+    pub plugins: Vec<Arc<dyn Plugin>>,
+    /// applied to the config in order right after the hook returns, so the following plugins see the patched config
+    pub config_patches: Vec<ConfigPatch>,
+}
+
 // MARK: - resolve
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase", default)]
@@ -186,8 +216,60 @@ pub struct PluginProcessModuleHookParam<'a> {
     pub meta: &'a mut ModuleMetaData,
 }
 
+// MARK: - ANALYZE DEPS
+pub struct PluginAnalyzeDepsHookParam<'a> {
+    pub module_id: &'a ModuleId,
+    pub resolved_path: &'a str,
+    pub module_type: &'a ModuleType,
+    /// the meta data after parse and process_module
+    pub meta: &'a ModuleMetaData,
+}
+
+// MARK: - GENERATE RESOURCES
+pub struct PluginGenerateResourcesHookParam<'a> {
+    /// name of the entry, the key of `Config.input`
+    pub entry_name: &'a str,
+    pub entry: &'a ModuleId,
+    /// statically reachable modules of the entry, sorted by execution order, dependencies come first
+    pub modules: &'a [ModuleId],
+    pub module_graph: &'a ModuleGraph,
+}
+
+// MARK: - UPDATE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateType {
+    Added,
+    Updated,
+    Removed,
+}
+
+pub struct PluginUpdateModulesHookParam {
+    /// changed files, absolute paths or paths relative to the root
+    pub paths: Vec<(String, UpdateType)>,
+}
+
+pub struct PluginModuleGraphUpdatedHookParam<'a> {
+    /// modules added to the module graph by the update, e.g. new dependencies of the updated modules
+    pub added_modules_ids: &'a [ModuleId],
+    /// modules removed from the module graph, including the dependencies that are no longer imported
+    pub removed_modules_ids: &'a [ModuleId],
+    /// modules rebuilt by the update
+    pub updated_modules_ids: &'a [ModuleId],
+}
+
 pub const DEFAULT_PRIORITY: i32 = 100;
 
+/// The phase a plugin is enforced to run in, plugins of the `Pre` phase run before `Normal` plugins,
+/// and plugins of the `Post` phase run after them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum PluginEnforce {
+    Pre,
+    #[default]
+    Normal,
+    Post,
+}
+
 #[async_trait]
 pub trait Plugin: Send + Sync {
     fn name(&self) -> &str;
@@ -196,7 +278,75 @@ pub trait Plugin: Send + Sync {
         DEFAULT_PRIORITY
     }
 
-    async fn config(&self, _config: &mut Config) -> Result<Option<()>> {
+    /// The phase of the plugin, plugins are sorted by phase first, then by [Plugin::priority] in the same phase.
+    fn enforce(&self) -> PluginEnforce {
+        PluginEnforce::Normal
+    }
+
+    /// Names of the plugins this plugin must run before. Unknown plugin names are ignored.
+    fn before(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Names of the plugins this plugin must run after. Unknown plugin names are ignored.
+    fn after(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Key of the plugin cache, the cache written by [Plugin::write_plugin_cache] is cleared when the key changes,
+    /// e.g. the version of the plugin or the options that affect the cached data.
+    fn plugin_cache_key(&self) -> String {
+        String::new()
+    }
+
+    /// Filters of the per module hooks, the plugin is skipped for modules that do not match the filter of the hook.
+    /// Called once when the plugin driver is created.
+    fn hook_filters(&self) -> PluginHookFilters {
+        PluginHookFilters::default()
+    }
+
+    /// Called before `build_start` with the cache written by [Plugin::write_plugin_cache] in the previous build,
+    /// only if the persistent cache is enabled and the cache exists.
+    async fn plugin_cache_loaded(
+        &self,
+        _cache: &[u8],
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called after `finish` if the persistent cache is enabled, the returned bytes are persisted for the next build.
+    async fn write_plugin_cache(
+        &self,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Called when a module is restored from the persistent cache, plugins are called serially.
+    /// Plugins can rebuild state derived from the cached [Module] here, e.g. interned ids or marks of custom meta data.
+    /// Return `Some(true)` to reject the cached module, it will be invalidated and rebuilt.
+    async fn handle_persistent_cached_module(
+        &self,
+        _module: &mut Module,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<bool>> {
+        Ok(None)
+    }
+
+    /// Called serially before the compilation context is created, with the config patched by the previous plugins.
+    /// Plugins returned here are added to the compiler and their `config` hooks are called after the existing plugins.
+    async fn config(&self, _config: &Config) -> Result<Option<PluginConfigHookResult>> {
+        Ok(None)
+    }
+
+    /// Called serially once the config is resolved, the config can not be modified any more.
+    async fn config_resolved(&self, _config: &Config) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called before the module graph is built, hooks of all plugins are called in parallel.
+    async fn build_start(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
         Ok(None)
     }
 
@@ -204,6 +354,7 @@ pub trait Plugin: Send + Sync {
         &self,
         _param: Arc<PluginResolveHookParam>,
         _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
     ) -> Result<Option<PluginResolveHookResult>> {
         Ok(None)
     }
@@ -212,6 +363,7 @@ pub trait Plugin: Send + Sync {
         &self,
         _param: Arc<PluginLoadHookParam>,
         _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
     ) -> Result<Option<PluginLoadHookResult>> {
         Ok(None)
     }
@@ -220,6 +372,7 @@ pub trait Plugin: Send + Sync {
         &self,
         _param: PluginTransformHookParam,
         _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
     ) -> Result<Option<PluginTransformHookResult>> {
         Ok(None)
     }
@@ -232,6 +385,8 @@ pub trait Plugin: Send + Sync {
         Ok(None)
     }
 
+    /// Called serially after the module is parsed. To edit the ast of script, css or html modules,
+    /// prefer registering an [crate::AstPass] to [CompilationContext::ast_passes], which runs after this hook.
     async fn process_module(
         &self,
         _param: &mut PluginProcessModuleHookParam,
@@ -239,4 +394,73 @@ pub trait Plugin: Send + Sync {
     ) -> Result<Option<ModuleMetaData>> {
         Ok(None)
     }
+
+    /// Analyze the dependencies of the parsed module, the first plugin that returns [Some] wins.
+    /// If no plugin handles the module, the compiler falls back to its built-in analyzers for script, css and html modules.
+    async fn analyze_deps(
+        &self,
+        _param: &PluginAnalyzeDepsHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<PluginAnalyzeDepsHookResultEntry>>> {
+        Ok(None)
+    }
+
+    /// Called after the module graph is built, hooks of all plugins are called in parallel.
+    async fn build_end(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called once the module graph is built and before any generation stage, plugins are called serially.
+    /// Plugins can merge modules, drop unreachable modules or rewrite edges of the graph here.
+    /// Note that [CompilationContext::module_graph] is locked while this hook is running, use `module_graph` instead.
+    async fn optimize_module_graph(
+        &self,
+        _module_graph: &mut ModuleGraph,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called serially when files are changed, before the modules of the changed files are rebuilt.
+    /// Plugins can extend `paths`, e.g. the modules generated from the changed file.
+    async fn update_modules(
+        &self,
+        _param: &mut PluginUpdateModulesHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called serially after the module graph is updated by the changed files, before the resources are regenerated.
+    async fn module_graph_updated(
+        &self,
+        _param: &PluginModuleGraphUpdatedHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called before resources are generated, hooks of all plugins are called in parallel.
+    async fn generate_start(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Generate resources for the entry, all plugins are called and the resources are merged.
+    async fn generate_resources(
+        &self,
+        _param: &PluginGenerateResourcesHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<Resource>>> {
+        Ok(None)
+    }
+
+    /// Called after all resources are generated, hooks of all plugins are called in parallel.
+    async fn generate_end(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    /// Called when the compilation is finished, hooks of all plugins are called in parallel.
+    async fn finish(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
+        Ok(None)
+    }
 }
@@ -0,0 +1,287 @@
+use std::{
+    any::type_name,
+    collections::hash_map::DefaultHasher,
+    ffi::{c_char, CStr},
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::Arc,
+};
+
+use libloading::Library;
+
+use crate::{
+    cache::module_cache::{immutable_modules::CachedPackage, CachedModule},
+    error::Result,
+    CompilationError, Config, Plugin,
+};
+
+/// Version of `toy_farm_core`, a native plugin must be built against the same version as the compiler.
+pub const CORE_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version of the rustc building `toy_farm_core`, exported by the build script. The layout of Rust types is not stable
+/// across compilers, so a native plugin must be built by the same rustc as the compiler.
+pub const RUSTC_VERSION: &str = env!("TOY_FARM_RUSTC_VERSION");
+/// NUL terminated [CORE_VERSION] and [RUSTC_VERSION], returned by the symbols of [export_native_plugin]
+#[doc(hidden)]
+pub const CORE_VERSION_NUL: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+#[doc(hidden)]
+pub const RUSTC_VERSION_NUL: &str = concat!(env!("TOY_FARM_RUSTC_VERSION"), "\0");
+
+const CORE_VERSION_SYMBOL: &[u8] = b"_toy_farm_core_version";
+const RUSTC_VERSION_SYMBOL: &[u8] = b"_toy_farm_rustc_version";
+const CACHE_LAYOUT_HASH_SYMBOL: &[u8] = b"_toy_farm_cache_layout_hash";
+const PLUGIN_CREATE_SYMBOL: &[u8] = b"_toy_farm_plugin_create";
+
+/// Hash of the archived layout of the cache items, it differs if the plugin is built against another version of rkyv
+/// or other definitions of the cache items.
+pub fn cache_layout_hash() -> u64 {
+    fn hash_layout<T>(hasher: &mut DefaultHasher) {
+        type_name::<T>().hash(hasher);
+        std::mem::size_of::<T>().hash(hasher);
+        std::mem::align_of::<T>().hash(hasher);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    hash_layout::<rkyv::Archived<CachedModule>>(&mut hasher);
+    hash_layout::<rkyv::Archived<CachedPackage>>(&mut hasher);
+
+    hasher.finish()
+}
+
+/// Export the symbols required by [load_native_plugin] from a `cdylib` plugin crate.
+/// The plugin type must implement `fn new(config: &Config, options: String) -> Self`, for example:
+/// ```ignore
+/// toy_farm_core::export_native_plugin!(MyPlugin);
+/// ```
+/// The symbols checked before the plugin is created use the C ABI, so that they can be called whichever rustc builds the plugin.
+#[macro_export]
+macro_rules! export_native_plugin {
+    ($plugin:ty) => {
+        #[no_mangle]
+        pub extern "C" fn _toy_farm_core_version() -> *const std::ffi::c_char {
+            $crate::plugin::native_plugin::CORE_VERSION_NUL.as_ptr() as _
+        }
+
+        #[no_mangle]
+        pub extern "C" fn _toy_farm_rustc_version() -> *const std::ffi::c_char {
+            $crate::plugin::native_plugin::RUSTC_VERSION_NUL.as_ptr() as _
+        }
+
+        #[no_mangle]
+        pub extern "C" fn _toy_farm_cache_layout_hash() -> u64 {
+            $crate::plugin::native_plugin::cache_layout_hash()
+        }
+
+        #[no_mangle]
+        pub fn _toy_farm_plugin_create(
+            config: &$crate::Config,
+            options: String,
+        ) -> std::sync::Arc<dyn $crate::Plugin> {
+            std::sync::Arc::new(<$plugin>::new(config, options))
+        }
+    };
+}
+
+/// Load a plugin from a shared library exported by [export_native_plugin].
+/// Return an error if the library can not be loaded, or it is built against an incompatible `toy_farm_core`, by another rustc,
+/// or with another layout of the cache items.
+pub fn load_native_plugin(
+    path: &Path,
+    config: &Config,
+    options: String,
+) -> Result<Arc<dyn Plugin>> {
+    let to_error = |msg: String| {
+        CompilationError::GenericError(format!(
+            "Failed to load native plugin `{}`: {}",
+            path.display(),
+            msg
+        ))
+    };
+
+    // SAFETY: the library is trusted by the user who configures it, and the exported symbols are checked below
+    let lib = unsafe { Library::new(path) }.map_err(|e| to_error(e.to_string()))?;
+    let plugin = unsafe {
+        let version = |symbol: &[u8]| {
+            lib.get::<extern "C" fn() -> *const c_char>(symbol)
+                .map(|version| CStr::from_ptr(version()).to_string_lossy().into_owned())
+                .map_err(|e| to_error(e.to_string()))
+        };
+        let core_version = version(CORE_VERSION_SYMBOL)?;
+        let rustc_version = version(RUSTC_VERSION_SYMBOL)?;
+        let cache_layout_hash = lib
+            .get::<extern "C" fn() -> u64>(CACHE_LAYOUT_HASH_SYMBOL)
+            .map_err(|e| to_error(e.to_string()))?;
+        check_native_plugin_abi(&core_version, &rustc_version, cache_layout_hash())
+            .map_err(to_error)?;
+
+        let create = lib
+            .get::<fn(&Config, String) -> Arc<dyn Plugin>>(PLUGIN_CREATE_SYMBOL)
+            .map_err(|e| to_error(e.to_string()))?;
+        create(config, options)
+    };
+
+    // the code and static data of the plugin live in the library, never unload it
+    std::mem::forget(lib);
+
+    Ok(plugin)
+}
+
+fn check_native_plugin_abi(
+    core_version: &str,
+    rustc_version: &str,
+    cache_layout_hash: u64,
+) -> std::result::Result<(), String> {
+    if core_version != CORE_VERSION {
+        return Err(format!(
+            "the plugin is built against toy_farm_core {}, but the compiler uses {}",
+            core_version, CORE_VERSION
+        ));
+    }
+
+    if rustc_version != RUSTC_VERSION {
+        return Err(format!(
+            "the plugin is built by {}, but the compiler is built by {}",
+            rustc_version, RUSTC_VERSION
+        ));
+    }
+
+    if cache_layout_hash != self::cache_layout_hash() {
+        return Err(
+            "the plugin is built with another layout of the cache items, rebuild it against the same rkyv and toy_farm_core"
+                .to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use crate::{CompilationError, Config};
+
+    use super::{
+        cache_layout_hash, check_native_plugin_abi, load_native_plugin, CORE_VERSION, RUSTC_VERSION,
+    };
+
+    /// Build a `cdylib` exporting the given abi symbols without `_toy_farm_plugin_create`, by the rustc in `PATH`
+    fn build_fixture(
+        name: &str,
+        core_version: &str,
+        rustc_version: &str,
+        layout_hash: u64,
+    ) -> PathBuf {
+        let dir = std::env::temp_dir().join("toy_farm_native_plugin_fixtures");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join(format!("{}.rs", name));
+        std::fs::write(
+            &source,
+            format!(
+                r#"
+#[no_mangle]
+pub extern "C" fn _toy_farm_core_version() -> *const u8 {{ "{}\0".as_ptr() }}
+#[no_mangle]
+pub extern "C" fn _toy_farm_rustc_version() -> *const u8 {{ "{}\0".as_ptr() }}
+#[no_mangle]
+pub extern "C" fn _toy_farm_cache_layout_hash() -> u64 {{ {} }}
+"#,
+                core_version, rustc_version, layout_hash
+            ),
+        )
+        .unwrap();
+
+        let lib = dir.join(format!(
+            "{}{}{}",
+            std::env::consts::DLL_PREFIX,
+            name,
+            std::env::consts::DLL_SUFFIX
+        ));
+        let status = Command::new("rustc")
+            .args(["--edition", "2021", "--crate-type", "cdylib", "-o"])
+            .arg(&lib)
+            .arg(&source)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        lib
+    }
+
+    fn load_error(path: &Path) -> String {
+        match load_native_plugin(path, &Config::default(), String::new()) {
+            Err(CompilationError::GenericError(msg)) => msg,
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("the fixture should not be loaded"),
+        }
+    }
+
+    #[test]
+    fn check_abi() {
+        assert!(check_native_plugin_abi(CORE_VERSION, RUSTC_VERSION, cache_layout_hash()).is_ok());
+        assert!(
+            check_native_plugin_abi("0.0.0-unknown", RUSTC_VERSION, cache_layout_hash())
+                .unwrap_err()
+                .contains("toy_farm_core 0.0.0-unknown")
+        );
+        assert!(
+            check_native_plugin_abi(CORE_VERSION, "rustc 0.0.0", cache_layout_hash())
+                .unwrap_err()
+                .contains("built by rustc 0.0.0")
+        );
+        assert!(
+            check_native_plugin_abi(CORE_VERSION, RUSTC_VERSION, cache_layout_hash() ^ 1)
+                .unwrap_err()
+                .contains("layout of the cache items")
+        );
+    }
+
+    #[test]
+    fn rustc_version() {
+        assert!(RUSTC_VERSION.starts_with("rustc "), "{}", RUSTC_VERSION);
+    }
+
+    #[test]
+    fn load_mismatched_fixture() {
+        let layout_hash = cache_layout_hash();
+
+        let lib = build_fixture(
+            "core_mismatched",
+            "0.0.0-unknown",
+            RUSTC_VERSION,
+            layout_hash,
+        );
+        assert!(load_error(&lib).contains("toy_farm_core 0.0.0-unknown"));
+
+        let lib = build_fixture("rustc_mismatched", CORE_VERSION, "rustc 0.0.0", layout_hash);
+        assert!(load_error(&lib).contains("built by rustc 0.0.0"));
+
+        let lib = build_fixture(
+            "layout_mismatched",
+            CORE_VERSION,
+            RUSTC_VERSION,
+            layout_hash ^ 1,
+        );
+        assert!(load_error(&lib).contains("layout of the cache items"));
+
+        // the abi matches, so the plugin is created by the missing symbol
+        let lib = build_fixture("abi_matched", CORE_VERSION, RUSTC_VERSION, layout_hash);
+        assert!(load_error(&lib).contains("_toy_farm_plugin_create"));
+    }
+
+    #[test]
+    fn load_missing_library() {
+        let result = load_native_plugin(
+            Path::new("/not/exists/libplugin.so"),
+            &Config::default(),
+            String::new(),
+        );
+
+        assert!(
+            matches!(result, Err(CompilationError::GenericError(msg)) if msg.contains("/not/exists/libplugin.so"))
+        );
+    }
+}
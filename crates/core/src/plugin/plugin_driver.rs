@@ -1,28 +1,40 @@
 use std::sync::Arc;
 
-use futures::Future;
+use futures::{future::try_join_all, Future};
 use toy_farm_utils::stringify_query;
 
 use crate::{
     error::Result,
-    record::{ModuleRecord, ResolveRecord, TransformRecord, Trigger},
-    CompilationContext, Config, ModuleMetaData, ModuleType, Plugin, PluginLoadHookParam,
-    PluginLoadHookResult, PluginResolveHookParam, PluginResolveHookResult,
-    PluginTransformHookParam, PluginTransformHookResult,
+    record::{
+        AnalyzeDepsRecord, ModuleRecord, ResolveRecord, ResourcePotRecord, TransformRecord, Trigger,
+    },
+    CompilationContext, CompilationError, Config, Module, ModuleGraph, ModuleMetaData, ModuleType,
+    Plugin, PluginAnalyzeDepsHookResultEntry, PluginLoadHookParam, PluginLoadHookResult,
+    PluginResolveHookParam, PluginResolveHookResult, PluginTransformHookParam,
+    PluginTransformHookResult,
 };
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::{PluginParseHookParam, PluginProcessModuleHookParam};
+use super::{
+    plugin_order::sort_plugins, PluginAnalyzeDepsHookParam, PluginGenerateResourcesHookParam,
+    PluginHookContext, PluginHookFilters, PluginModuleGraphUpdatedHookParam, PluginParseHookParam,
+    PluginProcessModuleHookParam, PluginUpdateModulesHookParam,
+};
 
 macro_rules! hook_first {
     (
         $func_name:ident,
         $ret_ty:ty,
+        $filter:expr,
         $callback:expr,
         $($arg:ident: $ty:ty),*
     ) => {
-        pub async fn $func_name<'a>(&self, $($arg: Arc<$ty>),*) -> $ret_ty {
-            for plugin in &self.plugins {
+        pub async fn $func_name(&self, $($arg: Arc<$ty>),*) -> $ret_ty {
+            for (plugin, filters) in self.plugins.iter().zip(&self.hook_filters) {
+                if !$filter(plugin.name(), filters, $(&$arg),*) {
+                    continue;
+                }
+
                 let start_time = SystemTime::now()
                     .duration_since(UNIX_EPOCH)
                     .expect("Time went backwards")
@@ -56,13 +68,17 @@ macro_rules! hook_first {
     };
 }
 macro_rules! hook_serial {
-    ($func_name:ident, $param_ty:ty, $callback:expr) => {
+    ($func_name:ident, $param_ty:ty, $filter:expr, $callback:expr) => {
         pub async fn $func_name(
             &self,
             param: $param_ty,
             context: &Arc<CompilationContext>,
         ) -> Result<()> {
-            for plugin in &self.plugins {
+            for (plugin, filters) in self.plugins.iter().zip(&self.hook_filters) {
+                if !$filter(filters, &*param, context) {
+                    continue;
+                }
+
                 let start_time = SystemTime::now()
                     .duration_since(UNIX_EPOCH)
                     .expect("Time went backwards")
@@ -87,35 +103,212 @@ macro_rules! hook_serial {
     };
 }
 
+/// Call the hook of all plugins concurrently, the hooks must be independent of each other.
+/// The duration of each plugin is recorded into [crate::record::RecordManager::plugin_stats].
+macro_rules! hook_parallel {
+    ($func_name:ident) => {
+        pub async fn $func_name(&self, context: &Arc<CompilationContext>) -> Result<()> {
+            try_join_all(self.plugins.iter().map(|plugin| async move {
+                let start_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("Time went backwards")
+                    .as_micros() as i64;
+
+                plugin.$func_name(context).await?;
+
+                let end_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("hook_parallel get end_time failed")
+                    .as_micros() as i64;
+
+                if self.record {
+                    context
+                        .record_manager
+                        .update_plugin_stats(
+                            plugin.name().to_string(),
+                            stringify!($func_name),
+                            end_time - start_time,
+                        )
+                        .await;
+                }
+
+                Ok::<(), CompilationError>(())
+            }))
+            .await?;
+
+            Ok(())
+        }
+    };
+}
+
 pub struct PluginDriver {
     plugins: Vec<Arc<dyn Plugin>>,
+    /// hook filters of the plugins, in the same order as `plugins`
+    hook_filters: Vec<PluginHookFilters>,
     record: bool,
 }
 
 impl PluginDriver {
-    pub fn new(mut plugins: Vec<Arc<dyn Plugin>>, record: bool) -> Self {
-        plugins.sort_by_key(|b| std::cmp::Reverse(b.priority()));
+    /// Create a plugin driver, the plugins are sorted by [sort_plugins].
+    /// Return an error if the order declarations of the plugins can not be satisfied.
+    pub fn new(plugins: Vec<Arc<dyn Plugin>>, record: bool) -> Result<Self> {
+        let plugins = sort_plugins(plugins)?;
+        let hook_filters = plugins.iter().map(|plugin| plugin.hook_filters()).collect();
+
+        Ok(Self {
+            plugins,
+            hook_filters,
+            record,
+        })
+    }
+
+    /// Names of the plugins in the final order their hooks are called.
+    pub fn plugin_order(&self) -> Vec<&str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    // MARK: CONFIG
+    /// Call `config` of the plugins in order and apply the returned config patches. Plugins returned by the hooks are
+    /// appended and their `config` hooks are called as well. Return all the plugins, including the added ones.
+    pub async fn resolve_config(
+        plugins: Vec<Arc<dyn Plugin>>,
+        config: &mut Config,
+    ) -> Result<Vec<Arc<dyn Plugin>>> {
+        let mut plugins = sort_plugins(plugins)?;
+        let mut index = 0;
+
+        while index < plugins.len() {
+            if let Some(result) = plugins[index].config(config).await? {
+                for patch in result.config_patches {
+                    patch(config);
+                }
+
+                plugins.extend(result.plugins);
+            }
+
+            index += 1;
+        }
+
+        Ok(plugins)
+    }
+
+    /// Call `config_resolved` of all plugins serially.
+    pub async fn config_resolved(&self, config: &Config) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.config_resolved(config).await?;
+        }
+
+        Ok(())
+    }
+
+    // MARK: PLUGIN CACHE
+    /// Read the cache of each plugin and call `plugin_cache_loaded` of the plugins whose cache exists.
+    pub async fn plugin_cache_loaded(&self, context: &Arc<CompilationContext>) -> Result<()> {
+        for plugin in &self.plugins {
+            let cache = context
+                .cache_manager
+                .read_plugin_cache(plugin.name(), &plugin.plugin_cache_key())
+                .await;
+
+            if let Some(cache) = cache {
+                plugin.plugin_cache_loaded(&cache, context).await?;
+            }
+        }
 
-        Self { plugins, record }
+        Ok(())
     }
 
-    pub async fn config(&self, config: &mut Config) -> Result<()> {
+    /// Call `write_plugin_cache` of all plugins and write the returned bytes to the plugin cache.
+    pub async fn write_plugin_cache(&self, context: &Arc<CompilationContext>) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.config(config).await?;
+            if let Some(cache) = plugin.write_plugin_cache(context).await? {
+                context
+                    .cache_manager
+                    .write_plugin_cache(plugin.name(), &plugin.plugin_cache_key(), cache)
+                    .await
+                    .map_err(|e| {
+                        CompilationError::GenericError(format!(
+                            "Failed to write the cache of plugin `{}`: {}",
+                            plugin.name(),
+                            e
+                        ))
+                    })?;
+            }
         }
+
         Ok(())
     }
 
+    /// Call `handle_persistent_cached_module` of all plugins serially, returns true if the cached module is rejected by any plugin.
+    /// The remaining plugins are skipped once the module is rejected as it will be rebuilt anyway.
+    pub async fn handle_persistent_cached_module(
+        &self,
+        module: &mut Module,
+        context: &Arc<CompilationContext>,
+    ) -> Result<bool> {
+        for plugin in &self.plugins {
+            let start_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_micros() as i64;
+
+            let should_invalidate = plugin
+                .handle_persistent_cached_module(module, context)
+                .await?;
+
+            if self.record {
+                let end_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("Time went backwards")
+                    .as_micros() as i64;
+
+                context
+                    .record_manager
+                    .update_plugin_stats(
+                        plugin.name().to_string(),
+                        "handle_persistent_cached_module",
+                        end_time - start_time,
+                    )
+                    .await;
+            }
+
+            if should_invalidate == Some(true) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    // MARK: LIFECYCLE
+    hook_parallel!(build_start);
+
+    hook_parallel!(build_end);
+
+    hook_parallel!(generate_start);
+
+    hook_parallel!(generate_end);
+
+    hook_parallel!(finish);
+
     // MARK: RESOLVE
     hook_first!(
         resolve,
         Result<Option<PluginResolveHookResult>>,
+        |plugin_name: &str,
+         filters: &PluginHookFilters,
+         param: &Arc<PluginResolveHookParam>,
+         _context: &Arc<CompilationContext>,
+         hook_context: &Arc<PluginHookContext>| {
+            !hook_context.is_caller(plugin_name) && filters.resolve.matches(None, None, Some(&param.kind))
+        },
         |result: Option<PluginResolveHookResult>,
          plugin_name: String,
          start_time: i64,
          end_time: i64,
          param: Arc<PluginResolveHookParam>,
-         context: Arc<CompilationContext>|
+         context: Arc<CompilationContext>,
+         _hook_context: Arc<PluginHookContext>|
         async move {
             if let Some(resolve_result) = result {
                 let full_path = resolve_result.resolved_path.clone() +
@@ -143,7 +336,8 @@ impl PluginDriver {
             }
         },
         param: PluginResolveHookParam,
-        context: CompilationContext
+        context: CompilationContext,
+        hook_context: PluginHookContext
     );
 
     // MARK: LOAD
@@ -151,12 +345,21 @@ impl PluginDriver {
     hook_first!(
         load,
         Result<Option<PluginLoadHookResult>>,
+        |plugin_name: &str,
+         filters: &PluginHookFilters,
+         param: &Arc<PluginLoadHookParam>,
+         _context: &Arc<CompilationContext>,
+         hook_context: &Arc<PluginHookContext>| {
+            !hook_context.is_caller(plugin_name)
+                && filters.load.matches(Some(&param.resolved_path), None, None)
+        },
         |result: Option<PluginLoadHookResult>,
          plugin_name: String,
          start_time: i64,
          end_time: i64,
          param: Arc<PluginLoadHookParam>,
-         context: Arc<CompilationContext>|
+         context: Arc<CompilationContext>,
+         _hook_context: Arc<PluginHookContext>|
         async move {
             if let Some(load_result) = result {
                 let full_path = format!("{}{}", param.resolved_path, stringify_query(&param.query));
@@ -181,43 +384,34 @@ impl PluginDriver {
             }
         },
         param: PluginLoadHookParam,
-        context: CompilationContext
+        context: CompilationContext,
+        hook_context: PluginHookContext
     );
 
     // MARK: TRANSFORM
+    /// Call `transform` of all plugins in order. Each plugin receives the `content`, `module_type` and
+    /// `source_map_chain` transformed by the previous plugins. The caller plugin of `hook_context` is skipped.
     pub async fn transform(
         &self,
-        param: PluginTransformHookParam,
+        mut param: PluginTransformHookParam,
         context: Arc<CompilationContext>,
+        hook_context: Arc<PluginHookContext>,
     ) -> Result<PluginDriverTransformHookResult> {
-        let mut result = PluginDriverTransformHookResult {
-            content: param.content.clone(),
-            source_map_chain: param.source_map_chain.clone(),
-            module_type: Some(param.module_type.clone()),
-        };
-
-        let transform_results = self.apply_transforms(param, context.clone()).await?;
-
-        for (plugin_name, plugin_result, duration) in transform_results {
-            if let Some(plugin_result) = plugin_result {
-                self.update_result(&mut result, &plugin_result);
-                self.record_transform(plugin_name, &result, duration, context.clone())
-                    .await;
-            }
-        }
-
-        Ok(result)
-    }
+        let record_id = format!("{}{}", param.resolved_path, stringify_query(&param.query));
 
-    async fn apply_transforms(
-        &self,
-        param: PluginTransformHookParam,
-        context: Arc<CompilationContext>,
-    ) -> Result<Vec<(String, Option<PluginTransformHookResult>, Option<i64>)>> {
-        let mut results = Vec::new();
+        for (plugin, filters) in self.plugins.iter().zip(&self.hook_filters) {
+            if hook_context.is_caller(plugin.name())
+                || !filters.transform.matches(
+                    Some(&param.resolved_path),
+                    Some(&param.module_type),
+                    None,
+                )
+            {
+                continue;
+            }
 
-        for plugin in &self.plugins {
-            let transform_future = plugin.transform(param.clone(), context.clone());
+            let transform_future =
+                plugin.transform(param.clone(), context.clone(), hook_context.clone());
             let (start_time, plugin_result) = self.measure_time(transform_future).await;
             let end_time = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -225,36 +419,51 @@ impl PluginDriver {
                 .as_micros() as i64;
             let duration = start_time.map(|start| end_time - start);
 
-            results.push((plugin.name().to_string(), plugin_result?, duration));
+            if let Some(plugin_result) = plugin_result? {
+                Self::update_param(&mut param, plugin_result);
+                self.record_transform(
+                    record_id.clone(),
+                    plugin.name().to_string(),
+                    &param,
+                    duration,
+                    context.clone(),
+                )
+                .await;
+            }
         }
 
-        Ok(results)
+        Ok(PluginDriverTransformHookResult {
+            content: param.content,
+            source_map_chain: param.source_map_chain,
+            module_type: Some(param.module_type),
+        })
     }
 
-    fn update_result(
-        &self,
-        result: &mut PluginDriverTransformHookResult,
-        plugin_result: &PluginTransformHookResult,
+    /// apply the result of a plugin to the param passed to the next plugin
+    fn update_param(
+        param: &mut PluginTransformHookParam,
+        plugin_result: PluginTransformHookResult,
     ) {
-        result.content.clone_from(&plugin_result.content);
-        if let Some(module_type) = &plugin_result.module_type {
-            result.module_type = Some(module_type.clone());
+        param.content = plugin_result.content;
+
+        if let Some(module_type) = plugin_result.module_type {
+            param.module_type = module_type;
         }
 
         if plugin_result.ignore_previous_source_map {
-            result.source_map_chain.clear();
+            param.source_map_chain.clear();
         }
 
-        if let Some(source_map) = &plugin_result.source_map {
-            let sourcemap = Arc::new(source_map.clone());
-            result.source_map_chain.push(sourcemap);
+        if let Some(source_map) = plugin_result.source_map {
+            param.source_map_chain.push(Arc::new(source_map));
         }
     }
 
     async fn record_transform(
         &self,
+        id: String,
         plugin_name: String,
-        result: &PluginDriverTransformHookResult,
+        param: &PluginTransformHookParam,
         duration: Option<i64>,
         context: Arc<CompilationContext>,
     ) {
@@ -271,16 +480,16 @@ impl PluginDriver {
         context
             .record_manager
             .add_transform_record(
-                result.content.clone(), // 注意：这里可能需要调整，因为我们不再有 param.resolved_path
+                id,
                 TransformRecord {
                     plugin: plugin_name,
                     hook: "transform".to_string(),
-                    content: result.content.clone(),
-                    source_maps: result
+                    content: param.content.clone(),
+                    source_maps: param
                         .source_map_chain
                         .last()
                         .map(|arc| arc.as_ref().clone()),
-                    module_type: result.module_type.clone().unwrap_or_default(),
+                    module_type: param.module_type.clone(),
                     trigger: Trigger::Compiler,
                     start_time,
                     end_time,
@@ -309,6 +518,14 @@ impl PluginDriver {
     hook_first!(
         parse,
         Result<Option<ModuleMetaData>>,
+        |_plugin_name: &str,
+         filters: &PluginHookFilters,
+         param: &Arc<PluginParseHookParam>,
+         _context: &Arc<CompilationContext>| {
+            filters
+                .parse
+                .matches(Some(&param.resolved_path), Some(&param.module_type), None)
+        },
         |_result: Option<ModuleMetaData>,
          plugin_name: String,
          start_time: i64,
@@ -344,6 +561,15 @@ impl PluginDriver {
     hook_serial!(
         process_module,
         &mut PluginProcessModuleHookParam<'_>,
+        |filters: &PluginHookFilters,
+         param: &PluginProcessModuleHookParam,
+         context: &Arc<CompilationContext>| {
+            filters.process_module.matches(
+                Some(&param.module_id.resolved_path(&context.config.root)),
+                Some(param.module_type),
+                None,
+            )
+        },
         |plugin_name: String,
          start_time: i64,
          end_time: i64,
@@ -372,6 +598,183 @@ impl PluginDriver {
             }
         }
     );
+
+    // MARK: ANALYZE_DEPS
+    pub async fn analyze_deps(
+        &self,
+        param: &PluginAnalyzeDepsHookParam<'_>,
+        context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<PluginAnalyzeDepsHookResultEntry>>> {
+        for (plugin, filters) in self.plugins.iter().zip(&self.hook_filters) {
+            if !filters.analyze_deps.matches(
+                Some(param.resolved_path),
+                Some(param.module_type),
+                None,
+            ) {
+                continue;
+            }
+
+            let start_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_micros() as i64;
+
+            let result = plugin.analyze_deps(param, context).await?;
+
+            let end_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("analyze_deps get end_time failed")
+                .as_micros() as i64;
+
+            if let Some(deps) = &result {
+                if self.record {
+                    let full_path =
+                        format!("{}{}", param.resolved_path, param.module_id.query_string());
+
+                    context
+                        .record_manager
+                        .add_analyze_deps_record(
+                            full_path,
+                            AnalyzeDepsRecord {
+                                plugin: plugin.name().to_string(),
+                                hook: "analyze_deps".to_string(),
+                                module_type: param.module_type.clone(),
+                                trigger: Trigger::Compiler,
+                                deps: deps.clone(),
+                                start_time,
+                                end_time,
+                                duration: end_time - start_time,
+                            },
+                        )
+                        .await;
+                }
+
+                return Ok(result);
+            }
+        }
+
+        Ok(None)
+    }
+
+    // MARK: OPTIMIZE_MODULE_GRAPH
+    hook_serial!(
+        optimize_module_graph,
+        &mut ModuleGraph,
+        |_filters: &PluginHookFilters,
+         _module_graph: &ModuleGraph,
+         _context: &Arc<CompilationContext>| true,
+        |plugin_name: String,
+         start_time: i64,
+         end_time: i64,
+         _module_graph: &ModuleGraph,
+         context: &Arc<CompilationContext>| {
+            let context = context.clone();
+            async move {
+                context
+                    .record_manager
+                    .update_plugin_stats(
+                        plugin_name,
+                        "optimize_module_graph",
+                        end_time - start_time,
+                    )
+                    .await;
+            }
+        }
+    );
+
+    // MARK: UPDATE
+    hook_serial!(
+        update_modules,
+        &mut PluginUpdateModulesHookParam,
+        |_filters: &PluginHookFilters,
+         _param: &PluginUpdateModulesHookParam,
+         _context: &Arc<CompilationContext>| true,
+        |plugin_name: String,
+         start_time: i64,
+         end_time: i64,
+         _param: &PluginUpdateModulesHookParam,
+         context: &Arc<CompilationContext>| {
+            let context = context.clone();
+            async move {
+                context
+                    .record_manager
+                    .update_plugin_stats(plugin_name, "update_modules", end_time - start_time)
+                    .await;
+            }
+        }
+    );
+
+    hook_serial!(
+        module_graph_updated,
+        &PluginModuleGraphUpdatedHookParam<'_>,
+        |_filters: &PluginHookFilters,
+         _param: &PluginModuleGraphUpdatedHookParam,
+         _context: &Arc<CompilationContext>| true,
+        |plugin_name: String,
+         start_time: i64,
+         end_time: i64,
+         _param: &PluginModuleGraphUpdatedHookParam,
+         context: &Arc<CompilationContext>| {
+            let context = context.clone();
+            async move {
+                context
+                    .record_manager
+                    .update_plugin_stats(plugin_name, "module_graph_updated", end_time - start_time)
+                    .await;
+            }
+        }
+    );
+
+    // MARK: GENERATE_RESOURCES
+    /// Call `generate_resources` of all plugins in order. Resources returned by a plugin are inserted into
+    /// [CompilationContext::resources_map] immediately, so the following plugins can reference them, e.g. the html plugin.
+    pub async fn generate_resources(
+        &self,
+        param: &PluginGenerateResourcesHookParam<'_>,
+        context: &Arc<CompilationContext>,
+    ) -> Result<()> {
+        for plugin in &self.plugins {
+            let Some(resources) = plugin.generate_resources(param, context).await? else {
+                continue;
+            };
+
+            if self.record {
+                context
+                    .record_manager
+                    .add_resource_pot_record(
+                        param.entry_name.to_string(),
+                        ResourcePotRecord {
+                            name: plugin.name().to_string(),
+                            hook: "generate_resources".to_string(),
+                            modules: param.modules.to_vec(),
+                            trigger: Trigger::Compiler,
+                            resources: resources
+                                .iter()
+                                .map(|resource| resource.name.clone())
+                                .collect(),
+                        },
+                    )
+                    .await;
+            }
+
+            let mut resources_map = context.resources_map.lock().await;
+
+            for resource in resources {
+                if resources_map.contains_key(&resource.name) {
+                    return Err(CompilationError::GenericError(format!(
+                        "Resource `{}` generated by plugin `{}` for entry `{}` conflicts with an existing resource",
+                        resource.name,
+                        plugin.name(),
+                        param.entry_name
+                    )));
+                }
+
+                resources_map.insert(resource.name.clone(), resource);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -380,3 +783,543 @@ pub struct PluginDriverTransformHookResult {
     pub source_map_chain: Vec<Arc<String>>,
     pub module_type: Option<ModuleType>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use tokio::sync::Barrier;
+
+    use crate::{
+        error::Result, CompilationContext, CompilationError, Config, Module, ModuleGraph,
+        ModuleType, Plugin, PluginConfigHookResult, PluginHookContext, PluginHookFilter,
+        PluginHookFilters, PluginResolveHookParam, PluginResolveHookResult,
+        PluginTransformHookParam, PluginTransformHookResult, ResolveKind,
+    };
+
+    use super::PluginDriver;
+
+    struct BarrierPlugin {
+        name: String,
+        barrier: Arc<Barrier>,
+    }
+
+    #[async_trait]
+    impl Plugin for BarrierPlugin {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn build_start(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
+            // deadlocks if the plugins are called serially
+            self.barrier.wait().await;
+            Ok(Some(()))
+        }
+
+        async fn finish(&self, _context: &Arc<CompilationContext>) -> Result<Option<()>> {
+            Err(CompilationError::GenericError(format!(
+                "{} failed",
+                self.name
+            )))
+        }
+    }
+
+    fn create_context() -> Arc<CompilationContext> {
+        let barrier = Arc::new(Barrier::new(2));
+        let plugins = ["a", "b"]
+            .into_iter()
+            .map(|name| {
+                Arc::new(BarrierPlugin {
+                    name: name.to_string(),
+                    barrier: barrier.clone(),
+                }) as _
+            })
+            .collect();
+
+        Arc::new(
+            CompilationContext::new(
+                Config {
+                    record: true,
+                    ..Default::default()
+                },
+                plugins,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn hook_parallel() {
+        let context = create_context();
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            context.plugin_driver.build_start(&context),
+        )
+        .await
+        .expect("plugins should be called in parallel")
+        .unwrap();
+
+        let plugin_stats = context.record_manager.plugin_stats.read().await;
+
+        for name in ["a", "b"] {
+            assert_eq!(plugin_stats[name]["build_start"].call_count, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn hook_parallel_error() {
+        let context = create_context();
+
+        assert!(matches!(
+            context.plugin_driver.finish(&context).await,
+            Err(CompilationError::GenericError(_))
+        ));
+    }
+
+    struct DropUnreachablePlugin;
+
+    #[async_trait]
+    impl Plugin for DropUnreachablePlugin {
+        fn name(&self) -> &str {
+            "DropUnreachablePlugin"
+        }
+
+        async fn optimize_module_graph(
+            &self,
+            module_graph: &mut ModuleGraph,
+            _context: &Arc<CompilationContext>,
+        ) -> Result<Option<()>> {
+            let (reachable, _) = module_graph.topo_sort();
+            let unreachable = module_graph
+                .modules()
+                .into_iter()
+                .map(|module| module.id.clone())
+                .filter(|id| !reachable.contains(id))
+                .collect::<Vec<_>>();
+
+            for id in unreachable {
+                module_graph.remove_module(&id);
+            }
+
+            Ok(Some(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn optimize_module_graph() {
+        let context = Arc::new(
+            CompilationContext::new(Config::default(), vec![Arc::new(DropUnreachablePlugin)])
+                .unwrap(),
+        );
+        let mut module_graph = ModuleGraph::new();
+        module_graph.add_module(Module::new("entry".into()));
+        module_graph.add_module(Module::new("unreachable".into()));
+        module_graph
+            .entries
+            .insert("entry".into(), "index".to_string());
+
+        context
+            .plugin_driver
+            .optimize_module_graph(&mut module_graph, &context)
+            .await
+            .unwrap();
+
+        assert!(module_graph.has_module(&"entry".into()));
+        assert!(!module_graph.has_module(&"unreachable".into()));
+    }
+
+    #[derive(Default)]
+    struct RehydratePlugin {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Plugin for RehydratePlugin {
+        fn name(&self) -> &str {
+            "RehydratePlugin"
+        }
+
+        async fn handle_persistent_cached_module(
+            &self,
+            module: &mut Module,
+            _context: &Arc<CompilationContext>,
+        ) -> Result<Option<bool>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            if module.module_type == ModuleType::Custom("stale".to_string()) {
+                return Ok(Some(true));
+            }
+
+            module.used_exports = vec!["default".to_string()];
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_persistent_cached_module() {
+        let first = Arc::new(RehydratePlugin::default());
+        let second = Arc::new(RehydratePlugin::default());
+        let context = Arc::new(
+            CompilationContext::new(Config::default(), vec![first.clone(), second.clone()])
+                .unwrap(),
+        );
+
+        let mut module = Module::new("a.js".into());
+        let should_invalidate = context
+            .plugin_driver
+            .handle_persistent_cached_module(&mut module, &context)
+            .await
+            .unwrap();
+
+        assert!(!should_invalidate);
+        assert_eq!(module.used_exports, vec!["default".to_string()]);
+        assert_eq!(second.calls.load(Ordering::SeqCst), 1);
+
+        let mut module = Module::new("b.stale".into());
+        module.module_type = ModuleType::Custom("stale".to_string());
+        let should_invalidate = context
+            .plugin_driver
+            .handle_persistent_cached_module(&mut module, &context)
+            .await
+            .unwrap();
+
+        assert!(should_invalidate);
+        assert_eq!(first.calls.load(Ordering::SeqCst), 2);
+        // the cached module is rejected, the remaining plugins are skipped
+        assert_eq!(second.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Default)]
+    struct CssTransformPlugin {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Plugin for CssTransformPlugin {
+        fn name(&self) -> &str {
+            "CssTransformPlugin"
+        }
+
+        fn hook_filters(&self) -> PluginHookFilters {
+            PluginHookFilters {
+                transform: PluginHookFilter {
+                    module_types: HashSet::from([ModuleType::Css]),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+
+        async fn transform(
+            &self,
+            param: PluginTransformHookParam,
+            _context: Arc<CompilationContext>,
+            _hook_context: Arc<PluginHookContext>,
+        ) -> Result<Option<PluginTransformHookResult>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            Ok(Some(PluginTransformHookResult {
+                content: format!("/* transformed */{}", param.content),
+                ..Default::default()
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn hook_filters() {
+        let plugin = Arc::new(CssTransformPlugin::default());
+        let context =
+            Arc::new(CompilationContext::new(Config::default(), vec![plugin.clone()]).unwrap());
+
+        for (resolved_path, module_type) in [
+            ("/root/index.ts", ModuleType::Ts),
+            ("/root/index.css", ModuleType::Css),
+        ] {
+            context
+                .plugin_driver
+                .transform(
+                    PluginTransformHookParam {
+                        module_id: resolved_path.to_string(),
+                        content: ".a {}".to_string(),
+                        module_type,
+                        resolved_path: resolved_path.to_string(),
+                        query: vec![],
+                        meta: Default::default(),
+                        source_map_chain: vec![],
+                    },
+                    context.clone(),
+                    Default::default(),
+                )
+                .await
+                .unwrap();
+        }
+
+        // the ts module is skipped without calling the plugin
+        assert_eq!(plugin.calls.load(Ordering::SeqCst), 1);
+    }
+
+    struct LessPlugin;
+
+    #[async_trait]
+    impl Plugin for LessPlugin {
+        fn name(&self) -> &str {
+            "LessPlugin"
+        }
+
+        fn priority(&self) -> i32 {
+            101
+        }
+
+        async fn transform(
+            &self,
+            param: PluginTransformHookParam,
+            _context: Arc<CompilationContext>,
+            _hook_context: Arc<PluginHookContext>,
+        ) -> Result<Option<PluginTransformHookResult>> {
+            if param.module_type != ModuleType::Custom("less".to_string()) {
+                return Ok(None);
+            }
+
+            Ok(Some(PluginTransformHookResult {
+                content: param.content.replace("@color", "red"),
+                module_type: Some(ModuleType::Css),
+                source_map: Some("less".to_string()),
+                ..Default::default()
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn transform_chain() {
+        let css_plugin = Arc::new(CssTransformPlugin::default());
+        let context = Arc::new(
+            CompilationContext::new(
+                Config::default(),
+                vec![css_plugin.clone(), Arc::new(LessPlugin)],
+            )
+            .unwrap(),
+        );
+
+        let result = context
+            .plugin_driver
+            .transform(
+                PluginTransformHookParam {
+                    module_id: "index.less".to_string(),
+                    content: ".a { color: @color }".to_string(),
+                    module_type: ModuleType::Custom("less".to_string()),
+                    resolved_path: "/root/index.less".to_string(),
+                    query: vec![],
+                    meta: Default::default(),
+                    source_map_chain: vec![Arc::new("load".to_string())],
+                },
+                context.clone(),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+
+        // the css plugin receives the content and module type transformed by the less plugin
+        assert_eq!(css_plugin.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(result.content, "/* transformed */.a { color: red }");
+        assert_eq!(result.module_type, Some(ModuleType::Css));
+        assert_eq!(
+            result.source_map_chain,
+            vec![Arc::new("load".to_string()), Arc::new("less".to_string())]
+        );
+    }
+
+    struct AliasPlugin;
+
+    #[async_trait]
+    impl Plugin for AliasPlugin {
+        fn name(&self) -> &str {
+            "AliasPlugin"
+        }
+
+        fn priority(&self) -> i32 {
+            101
+        }
+
+        async fn resolve(
+            &self,
+            param: Arc<PluginResolveHookParam>,
+            context: Arc<CompilationContext>,
+            hook_context: Arc<PluginHookContext>,
+        ) -> Result<Option<PluginResolveHookResult>> {
+            let Some(source) = param.source.strip_prefix("@/") else {
+                return Ok(None);
+            };
+            let mut meta = hook_context.meta.clone();
+            meta.insert("alias".to_string(), "@".to_string());
+
+            // delegate to the default resolver, the alias plugin itself is skipped
+            context
+                .plugin_driver
+                .resolve(
+                    Arc::new(PluginResolveHookParam {
+                        source: format!("./src/{}", source),
+                        ..(*param).clone()
+                    }),
+                    context.clone(),
+                    Arc::new(PluginHookContext {
+                        caller: Some(self.name().to_string()),
+                        meta,
+                    }),
+                )
+                .await
+        }
+    }
+
+    struct DefaultResolvePlugin;
+
+    #[async_trait]
+    impl Plugin for DefaultResolvePlugin {
+        fn name(&self) -> &str {
+            "DefaultResolvePlugin"
+        }
+
+        async fn resolve(
+            &self,
+            param: Arc<PluginResolveHookParam>,
+            _context: Arc<CompilationContext>,
+            hook_context: Arc<PluginHookContext>,
+        ) -> Result<Option<PluginResolveHookResult>> {
+            Ok(Some(PluginResolveHookResult {
+                resolved_path: format!("/root/{}", param.source.trim_start_matches("./")),
+                meta: hook_context.meta.clone(),
+                ..Default::default()
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn hook_context_caller() {
+        let context = Arc::new(
+            CompilationContext::new(
+                Config::default(),
+                vec![Arc::new(DefaultResolvePlugin), Arc::new(AliasPlugin)],
+            )
+            .unwrap(),
+        );
+
+        let result = context
+            .plugin_driver
+            .resolve(
+                Arc::new(PluginResolveHookParam {
+                    source: "@/index".to_string(),
+                    importer: None,
+                    kind: ResolveKind::Import,
+                }),
+                context.clone(),
+                Default::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result.resolved_path, "/root/src/index");
+        assert_eq!(result.meta.get("alias"), Some(&"@".to_string()));
+    }
+
+    #[derive(Default)]
+    struct FrameworkPlugin {
+        resolved: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Plugin for FrameworkPlugin {
+        fn name(&self) -> &str {
+            "FrameworkPlugin"
+        }
+
+        async fn config(&self, _config: &Config) -> Result<Option<PluginConfigHookResult>> {
+            Ok(Some(PluginConfigHookResult {
+                plugins: vec![Arc::new(SubPlugin {
+                    resolved: self.resolved.clone(),
+                })],
+                config_patches: vec![Box::new(|config| config.minify = true)],
+            }))
+        }
+    }
+
+    #[derive(Default)]
+    struct SubPlugin {
+        resolved: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Plugin for SubPlugin {
+        fn name(&self) -> &str {
+            "SubPlugin"
+        }
+
+        fn priority(&self) -> i32 {
+            200
+        }
+
+        async fn config(&self, config: &Config) -> Result<Option<PluginConfigHookResult>> {
+            if !config.minify {
+                return Err(CompilationError::GenericError(
+                    "config patches of the previous plugins should be applied".to_string(),
+                ));
+            }
+
+            Ok(Some(PluginConfigHookResult {
+                config_patches: vec![Box::new(|config| {
+                    config.custom.insert("sub".to_string(), "true".to_string());
+                })],
+                ..Default::default()
+            }))
+        }
+
+        async fn config_resolved(&self, config: &Config) -> Result<Option<()>> {
+            assert_eq!(config.custom.get("sub"), Some(&"true".to_string()));
+            self.resolved.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_config() {
+        let framework_plugin = Arc::new(FrameworkPlugin::default());
+        let mut config = Config::default();
+        let plugins = PluginDriver::resolve_config(vec![framework_plugin.clone()], &mut config)
+            .await
+            .unwrap();
+
+        assert!(config.minify);
+        assert_eq!(config.custom.get("sub"), Some(&"true".to_string()));
+
+        let context = CompilationContext::new(config, plugins).unwrap();
+        // the added plugin is sorted with the existing plugins
+        assert_eq!(
+            context.plugin_driver.plugin_order(),
+            vec!["SubPlugin", "FrameworkPlugin"]
+        );
+
+        context
+            .plugin_driver
+            .config_resolved(&context.config)
+            .await
+            .unwrap();
+        assert_eq!(framework_plugin.resolved.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn resolve_config_error() {
+        let mut config = Config::default();
+        let result =
+            PluginDriver::resolve_config(vec![Arc::new(SubPlugin::default())], &mut config).await;
+
+        assert!(matches!(result, Err(CompilationError::GenericError(_))));
+    }
+}
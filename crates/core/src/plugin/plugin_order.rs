@@ -0,0 +1,297 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    sync::Arc,
+};
+
+use crate::{error::Result, CompilationError, Plugin};
+
+/// Sort the plugins into a deterministic order:
+/// 1. plugins are grouped by [Plugin::enforce], `pre` plugins first and `post` plugins last.
+/// 2. in the same phase, plugins with higher [Plugin::priority] come first, ties keep the original order.
+/// 3. [Plugin::before] and [Plugin::after] declarations are then applied on top of the order above,
+///    declarations referring to plugins that are not registered are ignored.
+///
+/// Return an error naming the plugins if the declarations form a cycle or conflict with the enforce phases.
+pub(crate) fn sort_plugins(mut plugins: Vec<Arc<dyn Plugin>>) -> Result<Vec<Arc<dyn Plugin>>> {
+    // sort_by_key is stable, so ties keep the original order
+    plugins.sort_by_key(|plugin| (plugin.enforce(), Reverse(plugin.priority())));
+
+    let name_index_map = plugins
+        .iter()
+        .enumerate()
+        .map(|(i, plugin)| (plugin.name().to_string(), i))
+        .collect::<HashMap<_, _>>();
+    // edges[a] contains b means plugin a must run before plugin b
+    let mut edges = vec![vec![]; plugins.len()];
+
+    for (i, plugin) in plugins.iter().enumerate() {
+        let befores = plugin.before().into_iter().map(|name| (i, name, true));
+        let afters = plugin.after().into_iter().map(|name| (i, name, false));
+
+        for (i, name, is_before) in befores.chain(afters) {
+            let Some(&j) = name_index_map.get(&name) else {
+                continue;
+            };
+            let (from, to) = if is_before { (i, j) } else { (j, i) };
+
+            if plugins[from].enforce() > plugins[to].enforce() {
+                return Err(CompilationError::GenericError(format!(
+                    "Plugin `{}` ({:?}) can not run before plugin `{}` ({:?}), the declared order conflicts with their enforce phases",
+                    plugins[from].name(),
+                    plugins[from].enforce(),
+                    plugins[to].name(),
+                    plugins[to].enforce()
+                )));
+            }
+
+            edges[from].push(to);
+        }
+    }
+
+    let mut in_degrees = vec![0; plugins.len()];
+
+    for to in edges.iter().flatten() {
+        in_degrees[*to] += 1;
+    }
+
+    // always pick the ready plugin that comes first in the base order, so the result is deterministic
+    let mut ready = (0..plugins.len())
+        .filter(|i| in_degrees[*i] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut order = vec![];
+
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(i);
+
+        for &to in &edges[i] {
+            in_degrees[to] -= 1;
+
+            if in_degrees[to] == 0 {
+                ready.push(Reverse(to));
+            }
+        }
+    }
+
+    if order.len() < plugins.len() {
+        let cycle = find_cycle(&edges, &in_degrees)
+            .into_iter()
+            .map(|i| format!("`{}`", plugins[i].name()))
+            .collect::<Vec<_>>();
+
+        return Err(CompilationError::GenericError(format!(
+            "Plugins form a cycle with their before/after declarations: {}",
+            cycle.join(" -> ")
+        )));
+    }
+
+    let mut plugins = plugins.into_iter().map(Some).collect::<Vec<_>>();
+
+    Ok(order
+        .into_iter()
+        .map(|i| plugins[i].take().unwrap())
+        .collect())
+}
+
+/// find a cycle among the plugins that are not sorted, e.g. `[a, b, a]`
+fn find_cycle(edges: &[Vec<usize>], in_degrees: &[usize]) -> Vec<usize> {
+    let unsorted = |i: &usize| in_degrees[*i] > 0;
+    // every unsorted plugin has at least one unsorted predecessor, so walking backwards must end up in a cycle
+    let mut predecessors = vec![None; edges.len()];
+
+    for (from, tos) in edges.iter().enumerate().filter(|(from, _)| unsorted(from)) {
+        for to in tos.iter().filter(|to| unsorted(to)) {
+            predecessors[*to].get_or_insert(from);
+        }
+    }
+
+    let mut path = vec![];
+    let mut current = (0..edges.len())
+        .find(unsorted)
+        .expect("there should be unsorted plugins");
+
+    while !path.contains(&current) {
+        path.push(current);
+        current = predecessors[current].expect("unsorted plugins should have a predecessor");
+    }
+
+    let start = path.iter().position(|i| *i == current).unwrap();
+    let mut cycle = path[start..].to_vec();
+    cycle.reverse();
+    // start from the plugin that comes first in the base order, so the message is stable
+    let first = cycle.iter().enumerate().min_by_key(|(_, i)| **i).unwrap().0;
+    cycle.rotate_left(first);
+    cycle.push(cycle[0]);
+
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use crate::{CompilationError, Plugin, PluginEnforce, DEFAULT_PRIORITY};
+
+    use super::sort_plugins;
+
+    #[derive(Default)]
+    struct TestPlugin {
+        name: &'static str,
+        enforce: PluginEnforce,
+        priority: Option<i32>,
+        before: Vec<&'static str>,
+        after: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn priority(&self) -> i32 {
+            self.priority.unwrap_or(DEFAULT_PRIORITY)
+        }
+
+        fn enforce(&self) -> PluginEnforce {
+            self.enforce
+        }
+
+        fn before(&self) -> Vec<String> {
+            self.before.iter().map(|s| s.to_string()).collect()
+        }
+
+        fn after(&self) -> Vec<String> {
+            self.after.iter().map(|s| s.to_string()).collect()
+        }
+    }
+
+    fn sorted_names(plugins: Vec<TestPlugin>) -> crate::error::Result<Vec<String>> {
+        let plugins = plugins.into_iter().map(|p| Arc::new(p) as _).collect();
+
+        Ok(sort_plugins(plugins)?
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect())
+    }
+
+    #[test]
+    fn sort_by_enforce_and_priority() {
+        let names = sorted_names(vec![
+            TestPlugin {
+                name: "post",
+                enforce: PluginEnforce::Post,
+                priority: Some(1000),
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "normal_a",
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "normal_b",
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "normal_high",
+                priority: Some(101),
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "pre",
+                enforce: PluginEnforce::Pre,
+                priority: Some(0),
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(
+            names,
+            vec!["pre", "normal_high", "normal_a", "normal_b", "post"]
+        );
+    }
+
+    #[test]
+    fn sort_by_before_and_after() {
+        let names = sorted_names(vec![
+            TestPlugin {
+                name: "a",
+                after: vec!["c"],
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "b",
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "c",
+                // unknown plugins are ignored
+                after: vec!["unknown"],
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "d",
+                before: vec!["b"],
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(names, vec!["c", "a", "d", "b"]);
+    }
+
+    #[test]
+    fn report_cycle() {
+        let Err(CompilationError::GenericError(msg)) = sorted_names(vec![
+            TestPlugin {
+                name: "a",
+                before: vec!["b"],
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "b",
+                before: vec!["c"],
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "c",
+                before: vec!["a"],
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "d",
+                after: vec!["c"],
+                ..Default::default()
+            },
+        ]) else {
+            panic!("expect cycle error");
+        };
+
+        assert!(msg.ends_with("`a` -> `b` -> `c` -> `a`"), "{}", msg);
+    }
+
+    #[test]
+    fn report_enforce_conflict() {
+        let result = sorted_names(vec![
+            TestPlugin {
+                name: "pre",
+                enforce: PluginEnforce::Pre,
+                after: vec!["post"],
+                ..Default::default()
+            },
+            TestPlugin {
+                name: "post",
+                enforce: PluginEnforce::Post,
+                ..Default::default()
+            },
+        ]);
+
+        assert!(
+            matches!(result, Err(CompilationError::GenericError(msg)) if msg.contains("`post`") && msg.contains("`pre`"))
+        );
+    }
+}
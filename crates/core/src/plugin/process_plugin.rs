@@ -0,0 +1,394 @@
+//! Run a plugin in a child process, the plugin can be written in any language.
+//!
+//! The compiler talks to the child with [JSON-RPC 2.0](https://www.jsonrpc.org/specification) over stdio,
+//! each message is a single line of json terminated by `\n`. Requests are sent one at a time.
+//!
+//! 1. After spawning the child, the compiler sends an `initialize` request, `params` is `{ "root": "<Config.root>" }`.
+//!    The child replies with `{ "name": "my-plugin", "priority": 100, "hooks": ["resolve", "load", "transform"] }`,
+//!    `priority` is optional, `hooks` are the hooks the child implements, other hooks are skipped without a request.
+//! 2. Hooks are sent as requests whose `method` is the hook name and `params` is the hook param, for example
+//!    [PluginResolveHookParam] for `resolve`. The child replies with the hook result, or `null` to skip the module.
+//! 3. A child fails a hook by replying with an `error` object, `{ "code": 1, "message": "..." }`.
+//!
+//! When the compiler is dropped, stdin of the child is closed and the child should exit.
+
+use std::{collections::HashSet, error::Error, process::Stdio, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::Mutex,
+};
+
+use crate::{
+    error::Result, CompilationContext, CompilationError, Plugin, PluginHookContext,
+    PluginLoadHookParam, PluginLoadHookResult, PluginResolveHookParam, PluginResolveHookResult,
+    PluginTransformHookParam, PluginTransformHookResult, ProcessPluginConfig, DEFAULT_PRIORITY,
+};
+
+const JSONRPC_VERSION: &str = "2.0";
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a, P: Serialize> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: P,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    id: Option<u64>,
+    #[serde(default)]
+    result: Value,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct InitializeResult {
+    name: String,
+    priority: Option<i32>,
+    #[serde(default)]
+    hooks: HashSet<String>,
+}
+
+/// Error replied by the child process, used as the source of [CompilationError]s of the hooks.
+#[derive(Debug, thiserror::Error)]
+#[error("plugin `{plugin}` failed with code {code}: {message}")]
+pub struct ProcessPluginError {
+    pub plugin: String,
+    pub code: i64,
+    pub message: String,
+}
+
+struct ProcessIo {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+/// A [Plugin] adapter that forwards hooks to a child process, see the module doc for the protocol.
+pub struct ProcessPlugin {
+    name: String,
+    priority: i32,
+    hooks: HashSet<String>,
+    timeout: Duration,
+    io: Mutex<ProcessIo>,
+}
+
+impl ProcessPlugin {
+    /// Spawn the child process and wait for its reply of `initialize`.
+    pub async fn new(config: &ProcessPluginConfig, root: &str) -> Result<Self> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                CompilationError::GenericError(format!(
+                    "Failed to spawn plugin process `{}`: {}",
+                    config.command, e
+                ))
+            })?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+
+        let mut plugin = Self {
+            name: config.command.clone(),
+            priority: DEFAULT_PRIORITY,
+            hooks: HashSet::new(),
+            timeout: Duration::from_millis(config.timeout),
+            io: Mutex::new(ProcessIo {
+                child,
+                stdin,
+                stdout,
+                next_id: 0,
+            }),
+        };
+
+        let initialized: InitializeResult = plugin
+            .request("initialize", serde_json::json!({ "root": root }))
+            .await?
+            .map_err(|e| CompilationError::GenericError(e.to_string()))?
+            .ok_or_else(|| {
+                CompilationError::GenericError(format!(
+                    "Plugin process `{}` replied null to initialize",
+                    config.command
+                ))
+            })?;
+
+        plugin.name = initialized.name;
+        plugin.priority = initialized.priority.unwrap_or(DEFAULT_PRIORITY);
+        plugin.hooks = initialized.hooks;
+
+        Ok(plugin)
+    }
+
+    /// Hooks implemented by the child process.
+    pub fn hooks(&self) -> &HashSet<String> {
+        &self.hooks
+    }
+
+    /// Send a request and wait for the response. The outer error means the child is broken,
+    /// e.g. it exits, times out or replies invalid json, the inner error is replied by the child.
+    async fn request<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<std::result::Result<Option<R>, ProcessPluginError>> {
+        let mut io = self.io.lock().await;
+        let id = io.next_id;
+        io.next_id += 1;
+
+        let to_error = |msg: String| {
+            CompilationError::GenericError(format!(
+                "Plugin process `{}` failed when calling `{}`: {}",
+                self.name, method, msg
+            ))
+        };
+
+        let mut message = serde_json::to_string(&JsonRpcRequest {
+            jsonrpc: JSONRPC_VERSION,
+            id,
+            method,
+            params,
+        })
+        .map_err(|e| to_error(e.to_string()))?;
+        message.push('\n');
+
+        let exchange = async {
+            io.stdin.write_all(message.as_bytes()).await?;
+            io.stdin.flush().await?;
+            io.stdout.next_line().await
+        };
+
+        let line = match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(Ok(Some(line))) => line,
+            Ok(Ok(None)) => return Err(to_error("the process exited".to_string())),
+            Ok(Err(e)) => return Err(to_error(e.to_string())),
+            Err(_) => {
+                // the child may hang forever, kill it so later requests fail fast
+                let _ = io.child.start_kill();
+                return Err(to_error(format!("timed out after {:?}", self.timeout)));
+            }
+        };
+
+        let response: JsonRpcResponse =
+            serde_json::from_str(&line).map_err(|e| to_error(format!("{}: {}", e, line)))?;
+
+        if response.id != Some(id) {
+            return Err(to_error(format!(
+                "expected response of request {}, got {:?}",
+                id, response.id
+            )));
+        }
+
+        if let Some(error) = response.error {
+            return Ok(Err(ProcessPluginError {
+                plugin: self.name.clone(),
+                code: error.code,
+                message: error.message,
+            }));
+        }
+
+        serde_json::from_value(response.result)
+            .map(Ok)
+            .map_err(|e| to_error(e.to_string()))
+    }
+}
+
+fn boxed(e: ProcessPluginError) -> Option<Box<dyn Error + Send + Sync>> {
+    Some(Box::new(e))
+}
+
+#[async_trait]
+impl Plugin for ProcessPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    async fn resolve(
+        &self,
+        param: Arc<PluginResolveHookParam>,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginResolveHookResult>> {
+        if !self.hooks.contains("resolve") {
+            return Ok(None);
+        }
+
+        self.request("resolve", &*param)
+            .await?
+            .map_err(|e| CompilationError::ResolveError {
+                importer: param
+                    .importer
+                    .as_ref()
+                    .map_or_else(|| "unknown".to_string(), |importer| importer.to_string()),
+                src: param.source.clone(),
+                source: boxed(e),
+            })
+    }
+
+    async fn load(
+        &self,
+        param: Arc<PluginLoadHookParam>,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginLoadHookResult>> {
+        if !self.hooks.contains("load") {
+            return Ok(None);
+        }
+
+        self.request("load", &*param)
+            .await?
+            .map_err(|e| CompilationError::LoadError {
+                resolved_path: param.resolved_path.clone(),
+                source: boxed(e),
+            })
+    }
+
+    async fn transform(
+        &self,
+        param: PluginTransformHookParam,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginTransformHookResult>> {
+        if !self.hooks.contains("transform") {
+            return Ok(None);
+        }
+
+        self.request("transform", &param)
+            .await?
+            .map_err(|e| CompilationError::TransformError {
+                resolved_path: param.resolved_path.clone(),
+                msg: e.to_string(),
+            })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::{
+        CompilationContext, CompilationError, Config, ModuleType, Plugin, PluginLoadHookParam,
+        PluginResolveHookParam, PluginTransformHookParam, ResolveKind,
+    };
+
+    use crate::ProcessPluginConfig;
+
+    use super::ProcessPlugin;
+
+    /// a stub plugin that replies canned responses
+    const STUB: &str = r#"
+while IFS= read -r line; do
+  id=$(echo "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+  case "$line" in
+    *'"method":"initialize"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"name\":\"stub\",\"priority\":101,\"hooks\":[\"resolve\",\"load\",\"transform\"]}}" ;;
+    *'"method":"resolve"'*)
+      sleep 10 ;;
+    *'"method":"load"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":\"export default 1\",\"moduleType\":\"js\"}}" ;;
+    *'"method":"transform"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":2,\"message\":\"unexpected token\"}}" ;;
+  esac
+done
+"#;
+
+    async fn create_stub_plugin(timeout: u64) -> ProcessPlugin {
+        ProcessPlugin::new(
+            &ProcessPluginConfig {
+                command: "sh".to_string(),
+                args: vec!["-c".to_string(), STUB.to_string()],
+                timeout,
+            },
+            &std::env::temp_dir().to_string_lossy(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn call_hooks() {
+        let plugin = create_stub_plugin(5000).await;
+        let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+
+        assert_eq!(plugin.name(), "stub");
+        assert_eq!(plugin.priority(), 101);
+
+        let load_result = plugin
+            .load(
+                Arc::new(PluginLoadHookParam {
+                    module_id: "index.js".to_string(),
+                    resolved_path: "/root/index.js".to_string(),
+                    query: vec![],
+                    meta: HashMap::new(),
+                }),
+                context.clone(),
+                Default::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(load_result.content, "export default 1");
+        assert_eq!(load_result.module_type, ModuleType::Js);
+
+        let transform_result = plugin
+            .transform(
+                PluginTransformHookParam {
+                    module_id: "index.js".to_string(),
+                    content: "export default 1".to_string(),
+                    module_type: ModuleType::Js,
+                    resolved_path: "/root/index.js".to_string(),
+                    query: vec![],
+                    meta: HashMap::new(),
+                    source_map_chain: vec![],
+                },
+                context,
+                Default::default(),
+            )
+            .await;
+        assert!(
+            matches!(transform_result, Err(CompilationError::TransformError { resolved_path, msg }) if resolved_path == "/root/index.js" && msg.contains("unexpected token"))
+        );
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let plugin = create_stub_plugin(200).await;
+        let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+        let param = Arc::new(PluginResolveHookParam {
+            source: "./index".to_string(),
+            importer: None,
+            kind: ResolveKind::Import,
+        });
+
+        let result = plugin
+            .resolve(param.clone(), context.clone(), Default::default())
+            .await;
+        assert!(
+            matches!(result, Err(CompilationError::GenericError(msg)) if msg.contains("timed out"))
+        );
+
+        // the hung child is killed
+        let result = plugin.resolve(param, context, Default::default()).await;
+        assert!(matches!(result, Err(CompilationError::GenericError(_))));
+    }
+}
@@ -0,0 +1,444 @@
+//! Run a sandboxed plugin compiled to WebAssembly.
+//!
+//! A wasm plugin has no access to the host except the imports below, and every hook call runs in a fresh instance
+//! limited by [WasmPluginConfig::memory_limit] and [WasmPluginConfig::fuel], so no state is kept between calls.
+//!
+//! Exports of the wasm module:
+//! * `memory`: the linear memory.
+//! * `alloc(len: i32) -> i32`: allocate `len` bytes and return the pointer, used by the host to pass data to the plugin.
+//! * `resolve`, `load`, `transform`: `(ptr: i32, len: i32) -> i64`, optional, hooks that are not exported are skipped.
+//!   The input is the json of the hook param, e.g. [PluginLoadHookParam] for `load`. The output is a packed
+//!   `(ptr << 32) | len` pointing to a json envelope, `{ "result": <hook result or null> }` or `{ "error": "<message>" }`.
+//!
+//! Imports of the wasm module:
+//! * `env.read_file(ptr: i32, len: i32) -> i64`: read the file whose path is the utf8 string at `ptr`, relative to `Config.root`.
+//!   Return a packed `(ptr << 32) | len` of the content allocated by `alloc`, or `-1` if the file is outside of the root
+//!   or can not be read.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use wasmi::{
+    Caller, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use crate::{
+    error::Result, CompilationContext, CompilationError, Plugin, PluginHookContext,
+    PluginLoadHookParam, PluginLoadHookResult, PluginResolveHookParam, PluginResolveHookResult,
+    PluginTransformHookParam, PluginTransformHookResult, WasmPluginConfig,
+};
+
+const HOOKS: [&str; 3] = ["resolve", "load", "transform"];
+
+struct HostState {
+    /// canonicalized root, files outside of it can not be read
+    root: PathBuf,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn read_file(&self, path: &str) -> Option<Vec<u8>> {
+        let path = self.root.join(path).canonicalize().ok()?;
+
+        if !path.starts_with(&self.root) {
+            return None;
+        }
+
+        std::fs::read(path).ok()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum HookEnvelope {
+    Result(Value),
+    Error(String),
+}
+
+struct WasmRuntime {
+    name: String,
+    root: PathBuf,
+    engine: Engine,
+    module: Module,
+    linker: Linker<HostState>,
+    memory_limit: usize,
+    fuel: u64,
+}
+
+/// A [Plugin] that runs hooks in a wasm module, see the module doc for the abi.
+pub struct WasmPlugin {
+    hooks: Vec<&'static str>,
+    runtime: Arc<WasmRuntime>,
+}
+
+impl WasmPlugin {
+    /// Compile the wasm module at `config.path`, the path is relative to `root`.
+    pub fn new(config: &WasmPluginConfig, root: &str) -> Result<Self> {
+        let path = Path::new(root).join(&config.path);
+        let name = path
+            .file_stem()
+            .map_or_else(|| config.path.clone(), |s| s.to_string_lossy().to_string());
+        let to_error = |msg: String| {
+            CompilationError::GenericError(format!(
+                "Failed to load wasm plugin `{}`: {}",
+                name, msg
+            ))
+        };
+
+        let bytes = std::fs::read(&path).map_err(|e| to_error(format!("{:?}: {}", path, e)))?;
+        let root = Path::new(root)
+            .canonicalize()
+            .map_err(|e| to_error(format!("{}: {}", root, e)))?;
+
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, &bytes).map_err(|e| to_error(e.to_string()))?;
+
+        let mut linker = Linker::new(&engine);
+        linker
+            .func_wrap("env", "read_file", host_read_file)
+            .map_err(|e| to_error(e.to_string()))?;
+
+        let hooks = HOOKS
+            .into_iter()
+            .filter(|hook| module.get_export(hook).is_some())
+            .collect();
+
+        Ok(Self {
+            hooks,
+            runtime: Arc::new(WasmRuntime {
+                name,
+                root,
+                engine,
+                module,
+                linker,
+                memory_limit: config.memory_limit,
+                fuel: config.fuel,
+            }),
+        })
+    }
+
+    /// Hooks exported by the wasm module.
+    pub fn hooks(&self) -> &[&'static str] {
+        &self.hooks
+    }
+
+    async fn call_hook<P: Serialize, R: DeserializeOwned + Send + 'static>(
+        &self,
+        hook: &'static str,
+        param: &P,
+    ) -> Result<Option<R>> {
+        if !self.hooks.contains(&hook) {
+            return Ok(None);
+        }
+
+        let runtime = self.runtime.clone();
+        let input = serde_json::to_vec(param).map_err(|e| runtime.error(hook, e))?;
+
+        // wasm execution is cpu bound, do not block the async runtime
+        tokio::task::spawn_blocking(move || {
+            let output = runtime
+                .call(hook, &input)
+                .map_err(|e| runtime.error(hook, e))?;
+
+            match serde_json::from_slice(&output).map_err(|e| runtime.error(hook, e))? {
+                HookEnvelope::Result(result) => {
+                    serde_json::from_value(result).map_err(|e| runtime.error(hook, e))
+                }
+                HookEnvelope::Error(msg) => Err(runtime.error(hook, msg)),
+            }
+        })
+        .await?
+    }
+}
+
+impl WasmRuntime {
+    fn error(&self, hook: &str, e: impl ToString) -> CompilationError {
+        CompilationError::GenericError(format!(
+            "Wasm plugin `{}` failed in hook `{}`: {}",
+            self.name,
+            hook,
+            e.to_string()
+        ))
+    }
+
+    fn call(&self, hook: &str, input: &[u8]) -> std::result::Result<Vec<u8>, wasmi::Error> {
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                root: self.root.clone(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(self.memory_limit)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.fuel)?;
+
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+        let memory = exported_memory(&instance, &store)?;
+
+        let ptr = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")?
+            .call(&mut store, input.len() as i32)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+
+        let packed = instance
+            .get_typed_func::<(i32, i32), i64>(&store, hook)?
+            .call(&mut store, (ptr, input.len() as i32))?;
+        let (ptr, len) = unpack(packed);
+        let mut output = vec![0; len];
+        memory.read(&store, ptr, &mut output)?;
+
+        Ok(output)
+    }
+}
+
+fn exported_memory(
+    instance: &Instance,
+    store: &Store<HostState>,
+) -> std::result::Result<Memory, wasmi::Error> {
+    instance
+        .get_memory(store, "memory")
+        .ok_or_else(|| wasmi::Error::new("the module does not export `memory`"))
+}
+
+fn host_read_file(
+    mut caller: Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+) -> std::result::Result<i64, wasmi::Error> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("the module does not export `memory`"))?;
+    let mut path = vec![0; len as u32 as usize];
+    memory.read(&caller, ptr as u32 as usize, &mut path)?;
+
+    let Some(content) = String::from_utf8(path)
+        .ok()
+        .and_then(|path| caller.data().read_file(&path))
+    else {
+        return Ok(-1);
+    };
+
+    let alloc = caller
+        .get_export("alloc")
+        .and_then(Extern::into_func)
+        .ok_or_else(|| wasmi::Error::new("the module does not export `alloc`"))?
+        .typed::<i32, i32>(&caller)?;
+    let ptr = alloc.call(&mut caller, content.len() as i32)?;
+    memory.write(&mut caller, ptr as u32 as usize, &content)?;
+
+    Ok(pack(ptr as u32, content.len() as u32))
+}
+
+fn pack(ptr: u32, len: u32) -> i64 {
+    (((ptr as u64) << 32) | len as u64) as i64
+}
+
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+#[async_trait]
+impl Plugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.runtime.name
+    }
+
+    async fn resolve(
+        &self,
+        param: Arc<PluginResolveHookParam>,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginResolveHookResult>> {
+        self.call_hook("resolve", &*param).await
+    }
+
+    async fn load(
+        &self,
+        param: Arc<PluginLoadHookParam>,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginLoadHookResult>> {
+        self.call_hook("load", &*param).await
+    }
+
+    async fn transform(
+        &self,
+        param: PluginTransformHookParam,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginTransformHookResult>> {
+        self.call_hook("transform", &param).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use crate::{
+        CompilationContext, CompilationError, Config, ModuleType, Plugin, PluginLoadHookParam,
+        PluginResolveHookParam, PluginTransformHookParam, ResolveKind, WasmPluginConfig,
+    };
+
+    use super::WasmPlugin;
+
+    /// `load` returns the content of `result.json`, `resolve` tries to read a file outside of the root,
+    /// `transform` never ends.
+    const PLUGIN_WAT: &str = r#"
+(module
+  (import "env" "read_file" (func $read_file (param i32 i32) (result i64)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (data (i32.const 0) "result.json")
+  (data (i32.const 16) "../secret.json")
+  (data (i32.const 32) "{\"error\":\"permission denied\"}")
+  (func $alloc (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "load") (param i32 i32) (result i64)
+    (call $read_file (i32.const 0) (i32.const 11)))
+  (func (export "resolve") (param i32 i32) (result i64)
+    (local $result i64)
+    (local.set $result (call $read_file (i32.const 16) (i32.const 14)))
+    (if (i64.eq (local.get $result) (i64.const -1))
+      (then (return (i64.or (i64.shl (i64.const 32) (i64.const 32)) (i64.const 29)))))
+    (local.get $result))
+  (func (export "transform") (param i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (i64.const 0)))
+"#;
+
+    fn create_root(name: &str, wat: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("toy_farm_wasm_plugin_{}", name));
+        let root = dir.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("plugin.wasm"), wat::parse_str(wat).unwrap()).unwrap();
+        std::fs::write(
+            root.join("result.json"),
+            r#"{"result":{"content":"export default 1","moduleType":"js"}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("secret.json"), r#"{"result":null}"#).unwrap();
+
+        root
+    }
+
+    fn create_plugin(root: &Path, memory_limit: usize) -> WasmPlugin {
+        WasmPlugin::new(
+            &WasmPluginConfig {
+                path: "plugin.wasm".to_string(),
+                memory_limit,
+                fuel: 100_000,
+            },
+            &root.to_string_lossy(),
+        )
+        .unwrap()
+    }
+
+    fn transform_param() -> PluginTransformHookParam {
+        PluginTransformHookParam {
+            module_id: "index.js".to_string(),
+            content: "export default 1".to_string(),
+            module_type: ModuleType::Js,
+            resolved_path: "/root/index.js".to_string(),
+            query: vec![],
+            meta: HashMap::new(),
+            source_map_chain: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn call_hooks() {
+        let root = create_root("call_hooks", PLUGIN_WAT);
+        let plugin = create_plugin(&root, 1 << 20);
+        let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+
+        assert_eq!(plugin.name(), "plugin");
+        assert_eq!(plugin.hooks(), &["resolve", "load", "transform"]);
+
+        let load_result = plugin
+            .load(
+                Arc::new(PluginLoadHookParam {
+                    module_id: "index.js".to_string(),
+                    resolved_path: "/root/index.js".to_string(),
+                    query: vec![],
+                    meta: HashMap::new(),
+                }),
+                context.clone(),
+                Default::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(load_result.content, "export default 1");
+        assert_eq!(load_result.module_type, ModuleType::Js);
+
+        // files outside of the root can not be read
+        let resolve_result = plugin
+            .resolve(
+                Arc::new(PluginResolveHookParam {
+                    source: "./index".to_string(),
+                    importer: None,
+                    kind: ResolveKind::Import,
+                }),
+                context.clone(),
+                Default::default(),
+            )
+            .await;
+        assert!(
+            matches!(resolve_result, Err(CompilationError::GenericError(msg)) if msg.contains("`plugin`") && msg.contains("permission denied"))
+        );
+
+        // fuel is exhausted
+        let transform_result = plugin
+            .transform(transform_param(), context, Default::default())
+            .await;
+        assert!(
+            matches!(transform_result, Err(CompilationError::GenericError(msg)) if msg.contains("`plugin`") && msg.contains("all fuel consumed"))
+        );
+    }
+
+    #[tokio::test]
+    async fn memory_limit() {
+        let root = create_root(
+            "memory_limit",
+            r#"(module
+              (memory (export "memory") 32)
+              (func (export "alloc") (param i32) (result i32) (i32.const 0))
+              (func (export "transform") (param i32 i32) (result i64) (i64.const 0)))"#,
+        );
+        // 32 pages are 2MB
+        let plugin = create_plugin(&root, 1 << 20);
+        let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+
+        assert_eq!(plugin.hooks(), &["transform"]);
+
+        let result = plugin
+            .transform(transform_param(), context, Default::default())
+            .await;
+        assert!(
+            matches!(result, Err(CompilationError::GenericError(msg)) if msg.contains("`plugin`") && msg.contains("out of bounds memory allocation"))
+        );
+    }
+}
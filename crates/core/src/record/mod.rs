@@ -36,6 +36,11 @@ impl RecordManager {
         }
     }
 
+    /// Set the trigger of the following records, e.g. [Trigger::Update] while the compiler is handling updates.
+    pub async fn set_trigger(&self, trigger: Trigger) {
+        *self.trigger.write().await = trigger;
+    }
+
     pub async fn add_resolve_record(&self, source: String, mut record: ResolveRecord) {
         let mut resolve_id_map = self.resolve_id_map.write().await;
         self.update_plugin_stats(record.plugin.clone(), &record.hook.clone(), record.duration)
@@ -108,6 +113,32 @@ impl RecordManager {
             records.push(record);
         }
     }
+
+    pub async fn add_analyze_deps_record(&self, id: String, mut record: AnalyzeDepsRecord) {
+        let mut analyze_deps_map = self.analyze_deps_map.write().await;
+        self.update_plugin_stats(record.plugin.clone(), &record.hook.clone(), record.duration)
+            .await;
+        let trigger = self.trigger.read().await.to_owned();
+        record.trigger = trigger;
+        analyze_deps_map.entry(id).or_default().push(record);
+    }
+
+    pub async fn add_resource_pot_record(&self, id: String, mut record: ResourcePotRecord) {
+        let mut resource_pot_map = self.resource_pot_map.write().await;
+        let trigger = self.trigger.read().await.to_owned();
+        record.trigger = trigger;
+        resource_pot_map.entry(id).or_default().push(record);
+    }
+
+    pub async fn get_resource_pot_records_by_id(&self, id: &str) -> Vec<ResourcePotRecord> {
+        let resource_pot_map = self.resource_pot_map.read().await;
+        resource_pot_map.get(id).cloned().unwrap_or_default()
+    }
+
+    pub async fn get_analyze_deps_records_by_id(&self, id: &str) -> Vec<AnalyzeDepsRecord> {
+        let analyze_deps_map = self.analyze_deps_map.read().await;
+        analyze_deps_map.get(id).cloned().unwrap_or_default()
+    }
 }
 
 impl Default for RecordManager {
@@ -171,6 +202,7 @@ pub struct ResourcePotRecord {
     pub hook: String,
     pub modules: Vec<ModuleId>,
     pub resources: Vec<String>,
+    pub trigger: Trigger,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
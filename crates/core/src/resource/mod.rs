@@ -0,0 +1,42 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceType {
+    Js,
+    Css,
+    Html,
+    /// static assets, the value is the extension, e.g. `png`
+    Asset(String),
+    Custom(String),
+}
+
+impl ResourceType {
+    /// the extension of the generated file
+    pub fn to_ext(&self) -> String {
+        match self {
+            Self::Js => "js".to_string(),
+            Self::Css => "css".to_string(),
+            Self::Html => "html".to_string(),
+            Self::Asset(ext) | Self::Custom(ext) => ext.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_ext())
+    }
+}
+
+/// A generated resource of the compilation, e.g. `index.css`
+#[derive(Debug, Clone)]
+pub struct Resource {
+    /// the file name of the resource, it's unique in the compilation
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub resource_type: ResourceType,
+    /// name of the entry that this resource is generated for, [None] if it's not related to any entry
+    pub entry: Option<String>,
+}
@@ -0,0 +1,171 @@
+use std::{collections::HashSet, sync::Arc};
+
+use async_trait::async_trait;
+use toy_farm_core::{
+    error::Result, CompilationContext, CssModuleMetaData, ModuleMetaData, ModuleType, Plugin,
+    PluginAnalyzeDepsHookParam, PluginAnalyzeDepsHookResultEntry, PluginGenerateResourcesHookParam,
+    PluginHookFilter, PluginHookFilters, PluginParseHookParam, Resource, ResourceType,
+};
+use toy_farm_toolkit::css::{
+    analyze_deps::analyze_deps,
+    codegen_css_stylesheet, parse_css_stylesheet,
+    swc_css_ast::{AtRulePrelude, ImportHref, Rule, Stylesheet, UrlValue},
+    ParseCssModuleResult,
+};
+use toy_farm_utils::is_remote_or_inline_url;
+
+/// Built-in css plugin, parse css modules into [CssModuleMetaData] and concatenate the css modules of each entry
+/// into a single css resource, in the import order.
+pub struct FarmPluginCss {}
+
+impl FarmPluginCss {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for FarmPluginCss {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for FarmPluginCss {
+    fn name(&self) -> &str {
+        "FarmPluginCss"
+    }
+
+    fn priority(&self) -> i32 {
+        99
+    }
+
+    fn hook_filters(&self) -> PluginHookFilters {
+        let filter = PluginHookFilter {
+            module_types: HashSet::from([ModuleType::Css]),
+            ..Default::default()
+        };
+
+        PluginHookFilters {
+            parse: filter.clone(),
+            analyze_deps: filter,
+            ..Default::default()
+        }
+    }
+
+    async fn parse(
+        &self,
+        param: Arc<PluginParseHookParam>,
+        _context: Arc<CompilationContext>,
+    ) -> Result<Option<ModuleMetaData>> {
+        if param.module_type != ModuleType::Css {
+            return Ok(None);
+        }
+
+        let ParseCssModuleResult { ast, comments } =
+            parse_css_stylesheet(&param.module_id.to_string(), &param.content)?;
+
+        Ok(Some(ModuleMetaData::Css(CssModuleMetaData {
+            ast,
+            comments: comments.into(),
+        })))
+    }
+
+    async fn analyze_deps(
+        &self,
+        param: &PluginAnalyzeDepsHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<PluginAnalyzeDepsHookResultEntry>>> {
+        let ModuleMetaData::Css(css) = param.meta else {
+            return Ok(None);
+        };
+
+        Ok(Some(analyze_deps(&css.ast)))
+    }
+
+    async fn generate_resources(
+        &self,
+        param: &PluginGenerateResourcesHookParam,
+        context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<Resource>>> {
+        let mut remote_imports = vec![];
+        let mut rules = vec![];
+
+        // the modules are sorted by execution order, so the imported css comes before the importer
+        for module_id in param.modules {
+            let Some(module) = param.module_graph.module(module_id) else {
+                continue;
+            };
+
+            if module.module_type != ModuleType::Css {
+                continue;
+            }
+
+            let mut stylesheet = module.meta.as_css().ast.clone();
+            // local @import are resolved as dependencies and already concatenated
+            stylesheet.rules.retain(|rule| match import_href(rule) {
+                Some(href) if is_remote_or_inline_url(&href) => {
+                    remote_imports.push(rule.clone());
+                    false
+                }
+                Some(_) => false,
+                None => true,
+            });
+
+            rules.push(stylesheet);
+        }
+
+        if rules.is_empty() && remote_imports.is_empty() {
+            return Ok(None);
+        }
+
+        let minify = context.config.minify;
+        // @import must precede all other rules, so remote @import are hoisted to the top
+        let mut code = vec![];
+
+        if !remote_imports.is_empty() {
+            code.push(codegen_css_stylesheet(
+                &Stylesheet {
+                    span: Default::default(),
+                    rules: remote_imports,
+                },
+                minify,
+            ));
+        }
+
+        code.extend(
+            rules
+                .iter()
+                .filter(|stylesheet| !stylesheet.rules.is_empty())
+                .map(|stylesheet| codegen_css_stylesheet(stylesheet, minify)),
+        );
+
+        Ok(Some(vec![Resource {
+            name: context
+                .config
+                .output
+                .resource_filename(param.entry_name, &ResourceType::Css.to_ext()),
+            bytes: code.join("\n").into_bytes(),
+            resource_type: ResourceType::Css,
+            entry: Some(param.entry_name.to_string()),
+        }]))
+    }
+}
+
+/// get the href of `@import 'href'`, [None] if the rule is not an @import rule
+fn import_href(rule: &Rule) -> Option<String> {
+    let Rule::AtRule(at_rule) = rule else {
+        return None;
+    };
+    let Some(AtRulePrelude::ImportPrelude(prelude)) = at_rule.prelude.as_deref() else {
+        return None;
+    };
+
+    match &*prelude.href {
+        ImportHref::Url(url) => url.value.as_ref().map(|value| match &**value {
+            UrlValue::Str(str) => str.value.to_string(),
+            UrlValue::Raw(raw) => raw.value.to_string(),
+        }),
+        ImportHref::Str(str) => Some(str.value.to_string()),
+    }
+}
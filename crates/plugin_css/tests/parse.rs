@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use toy_farm_core::{
+    CompilationContext, CompilationError, Config, ModuleMetaData, ModuleType, Plugin,
+    PluginAnalyzeDepsHookParam, PluginParseHookParam, ResolveKind,
+};
+use toy_farm_plugin_css::FarmPluginCss;
+
+async fn parse(
+    content: &str,
+    module_type: ModuleType,
+) -> toy_farm_core::error::Result<Option<ModuleMetaData>> {
+    let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+    let param = PluginParseHookParam {
+        module_id: "index.css".into(),
+        resolved_path: "/root/index.css".to_string(),
+        query: vec![],
+        module_type,
+        content: Arc::new(content.to_string()),
+    };
+
+    FarmPluginCss::new().parse(Arc::new(param), context).await
+}
+
+#[tokio::test]
+async fn parse_css_and_keep_comments() {
+    let meta = parse("/* leading */\n.a { color: red; }", ModuleType::Css)
+        .await
+        .unwrap()
+        .unwrap();
+    let css = meta.as_css();
+
+    assert_eq!(css.ast.rules.len(), 1);
+    assert_eq!(css.comments.leading.len(), 1);
+
+    // non css modules are left to other plugins
+    assert!(parse("const a = 1;", ModuleType::Js)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn analyze_css_deps() {
+    let meta = parse(
+        "@import './a.css';\n@import 'https://a.com/b.css';\n.a { background: url('./a.png'); }",
+        ModuleType::Css,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+
+    let deps = FarmPluginCss::new()
+        .analyze_deps(
+            &PluginAnalyzeDepsHookParam {
+                module_id: &"index.css".into(),
+                resolved_path: "/root/index.css",
+                module_type: &ModuleType::Css,
+                meta: &meta,
+            },
+            &context,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        deps.into_iter()
+            .map(|dep| (dep.source, dep.kind))
+            .collect::<Vec<_>>(),
+        vec![
+            ("./a.css".to_string(), ResolveKind::CssAtImport),
+            ("./a.png".to_string(), ResolveKind::CssUrl),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn report_parse_error_position() {
+    let Err(err) = parse(".a { color: red; }\n.b { color: red;", ModuleType::Css).await else {
+        panic!("expect parse error");
+    };
+
+    match err {
+        CompilationError::ParseError { resolved_path, msg } => {
+            assert_eq!(resolved_path, "index.css");
+            assert!(msg.starts_with("index.css:2:"), "{}", msg);
+        }
+        _ => panic!("expect parse error, got {:?}", err),
+    }
+}
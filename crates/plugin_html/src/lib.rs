@@ -0,0 +1,226 @@
+use std::{collections::HashSet, sync::Arc};
+
+use async_trait::async_trait;
+use toy_farm_core::{
+    error::Result, CompilationContext, HtmlModuleMetaData, ModuleMetaData, ModuleType, Plugin,
+    PluginAnalyzeDepsHookParam, PluginAnalyzeDepsHookResultEntry, PluginGenerateResourcesHookParam,
+    PluginHookFilter, PluginHookFilters, PluginParseHookParam, Resource, ResourceType,
+};
+use toy_farm_toolkit::{
+    html::{
+        analyze_deps::{analyze_deps, get_element_attr},
+        codegen_html_document, parse_html_document, remove_insignificant_whitespace,
+        swc_html_ast::{Attribute, Child, Element, Namespace},
+        swc_html_visit::{VisitMut, VisitMutWith},
+    },
+    script::swc_common::DUMMY_SP,
+};
+use toy_farm_utils::is_remote_or_inline_url;
+
+/// Built-in html plugin, parse html modules into [HtmlModuleMetaData] and generate the html resource of html entries.
+/// The local `<script src>` and `<link href>` of the entry are replaced by the script and css resources generated for the entry.
+pub struct FarmPluginHtml {}
+
+impl FarmPluginHtml {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for FarmPluginHtml {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for FarmPluginHtml {
+    fn name(&self) -> &str {
+        "FarmPluginHtml"
+    }
+
+    /// generate resources after the script and css plugins, so that the html can reference their resources
+    fn priority(&self) -> i32 {
+        98
+    }
+
+    fn hook_filters(&self) -> PluginHookFilters {
+        let filter = PluginHookFilter {
+            module_types: HashSet::from([ModuleType::Html]),
+            ..Default::default()
+        };
+
+        PluginHookFilters {
+            parse: filter.clone(),
+            analyze_deps: filter,
+            ..Default::default()
+        }
+    }
+
+    async fn parse(
+        &self,
+        param: Arc<PluginParseHookParam>,
+        _context: Arc<CompilationContext>,
+    ) -> Result<Option<ModuleMetaData>> {
+        if param.module_type != ModuleType::Html {
+            return Ok(None);
+        }
+
+        let ast = parse_html_document(&param.module_id.to_string(), &param.content)?;
+
+        Ok(Some(ModuleMetaData::Html(HtmlModuleMetaData { ast })))
+    }
+
+    async fn analyze_deps(
+        &self,
+        param: &PluginAnalyzeDepsHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<PluginAnalyzeDepsHookResultEntry>>> {
+        let ModuleMetaData::Html(html) = param.meta else {
+            return Ok(None);
+        };
+
+        Ok(Some(analyze_deps(&html.ast)))
+    }
+
+    async fn generate_resources(
+        &self,
+        param: &PluginGenerateResourcesHookParam,
+        context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<Resource>>> {
+        let Some(entry) = param.module_graph.module(param.entry) else {
+            return Ok(None);
+        };
+
+        if entry.module_type != ModuleType::Html {
+            return Ok(None);
+        }
+
+        let mut script_resources = vec![];
+        let mut css_resources = vec![];
+
+        for resource in context.resources_map.lock().await.values() {
+            if resource.entry.as_deref() != Some(param.entry_name) {
+                continue;
+            }
+
+            match resource.resource_type {
+                ResourceType::Js => script_resources.push(resource.name.clone()),
+                ResourceType::Css => css_resources.push(resource.name.clone()),
+                _ => {}
+            }
+        }
+
+        // make sure the output is stable
+        script_resources.sort();
+        css_resources.sort();
+
+        let public_path = &context.config.output.public_path;
+        let mut document = entry.meta.as_html().ast.clone();
+        document.visit_mut_with(&mut ResourcesInjector {
+            script_urls: script_resources
+                .iter()
+                .map(|name| public_url(public_path, name))
+                .collect(),
+            css_urls: css_resources
+                .iter()
+                .map(|name| public_url(public_path, name))
+                .collect(),
+        });
+
+        if context.config.minify {
+            remove_insignificant_whitespace(&mut document);
+        }
+
+        Ok(Some(vec![Resource {
+            name: context
+                .config
+                .output
+                .entry_resource_filename(param.entry_name, &ResourceType::Html.to_ext()),
+            bytes: codegen_html_document(&document, context.config.minify).into_bytes(),
+            resource_type: ResourceType::Html,
+            entry: Some(param.entry_name.to_string()),
+        }]))
+    }
+}
+
+/// join the public path and the resource name, e.g. `/` + `index.css` -> `/index.css`
+fn public_url(public_path: &str, resource_name: &str) -> String {
+    if public_path.is_empty() || public_path.ends_with('/') {
+        format!("{}{}", public_path, resource_name)
+    } else {
+        format!("{}/{}", public_path, resource_name)
+    }
+}
+
+/// Remove the local `<script src>` and `<link rel="stylesheet" href>` which are bundled into the generated resources,
+/// then inject the generated css resources at the end of `<head>` and the script resources at the end of `<body>`.
+struct ResourcesInjector {
+    script_urls: Vec<String>,
+    css_urls: Vec<String>,
+}
+
+impl ResourcesInjector {
+    fn is_bundled_dep(child: &Child) -> bool {
+        let Child::Element(element) = child else {
+            return false;
+        };
+
+        let source = match &*element.tag_name {
+            "script" => get_element_attr(element, "src"),
+            "link" => {
+                let is_stylesheet = get_element_attr(element, "rel")
+                    .is_some_and(|rel| rel.split_whitespace().any(|r| r == "stylesheet"));
+
+                get_element_attr(element, "href").filter(|_| is_stylesheet)
+            }
+            _ => None,
+        };
+
+        source.is_some_and(|source| !is_remote_or_inline_url(source))
+    }
+
+    fn create_element(tag_name: &str, attributes: Vec<(&str, &str)>) -> Child {
+        Child::Element(Element {
+            span: DUMMY_SP,
+            tag_name: tag_name.into(),
+            namespace: Namespace::HTML,
+            attributes: attributes
+                .into_iter()
+                .map(|(name, value)| Attribute {
+                    span: DUMMY_SP,
+                    namespace: None,
+                    prefix: None,
+                    name: name.into(),
+                    raw_name: None,
+                    value: Some(value.into()),
+                    raw_value: None,
+                })
+                .collect(),
+            children: vec![],
+            content: None,
+            is_self_closing: false,
+        })
+    }
+}
+
+impl VisitMut for ResourcesInjector {
+    fn visit_mut_element(&mut self, element: &mut Element) {
+        element
+            .children
+            .retain(|child| !Self::is_bundled_dep(child));
+        element.visit_mut_children_with(self);
+
+        match &*element.tag_name {
+            "head" => element.children.extend(self.css_urls.iter().map(|url| {
+                Self::create_element("link", vec![("rel", "stylesheet"), ("href", url)])
+            })),
+            "body" => element.children.extend(
+                self.script_urls
+                    .iter()
+                    .map(|url| Self::create_element("script", vec![("src", url)])),
+            ),
+            _ => {}
+        }
+    }
+}
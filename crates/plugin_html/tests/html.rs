@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use toy_farm_core::{
+    CompilationContext, Config, Module, ModuleGraph, ModuleMetaData, ModuleType, OutputConfig,
+    Plugin, PluginAnalyzeDepsHookParam, PluginGenerateResourcesHookParam, PluginParseHookParam,
+    ResolveKind, Resource, ResourceType,
+};
+use toy_farm_plugin_html::FarmPluginHtml;
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <link rel="stylesheet" href="./index.css">
+  <link rel="stylesheet" href="https://a.com/remote.css">
+</head>
+<body>
+  <script src="./index.ts"></script>
+</body>
+</html>"#;
+
+async fn parse(content: &str, module_type: ModuleType) -> Option<ModuleMetaData> {
+    let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+    let param = PluginParseHookParam {
+        module_id: "index.html".into(),
+        resolved_path: "/root/index.html".to_string(),
+        query: vec![],
+        module_type,
+        content: Arc::new(content.to_string()),
+    };
+
+    FarmPluginHtml::new()
+        .parse(Arc::new(param), context)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn analyze_html_deps() {
+    let meta = parse(HTML, ModuleType::Html).await.unwrap();
+    let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+
+    let deps = FarmPluginHtml::new()
+        .analyze_deps(
+            &PluginAnalyzeDepsHookParam {
+                module_id: &"index.html".into(),
+                resolved_path: "/root/index.html",
+                module_type: &ModuleType::Html,
+                meta: &meta,
+            },
+            &context,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        deps.into_iter()
+            .map(|dep| (dep.source, dep.kind))
+            .collect::<Vec<_>>(),
+        vec![
+            ("./index.css".to_string(), ResolveKind::LinkHref),
+            ("./index.ts".to_string(), ResolveKind::ScriptSrc),
+        ]
+    );
+
+    // non html modules are left to other plugins
+    assert!(parse(".a {}", ModuleType::Css).await.is_none());
+}
+
+#[tokio::test]
+async fn rewrite_html_with_generated_resources() {
+    let context = Arc::new(
+        CompilationContext::new(
+            Config {
+                output: OutputConfig {
+                    public_path: "/assets".to_string(),
+                    ..Default::default()
+                },
+                minify: true,
+                ..Default::default()
+            },
+            vec![],
+        )
+        .unwrap(),
+    );
+
+    {
+        let mut resources_map = context.resources_map.lock().await;
+
+        for (name, resource_type) in [
+            ("index.js", ResourceType::Js),
+            ("index.css", ResourceType::Css),
+        ] {
+            resources_map.insert(
+                name.to_string(),
+                Resource {
+                    name: name.to_string(),
+                    bytes: vec![],
+                    resource_type,
+                    entry: Some("index".to_string()),
+                },
+            );
+        }
+    }
+
+    let entry = "index.html".into();
+    let mut module = Module::new("index.html".into());
+    module.module_type = ModuleType::Html;
+    *module.meta = parse(HTML, ModuleType::Html).await.unwrap();
+
+    let mut module_graph = ModuleGraph::new();
+    module_graph.add_module(module);
+
+    let resources = FarmPluginHtml::new()
+        .generate_resources(
+            &PluginGenerateResourcesHookParam {
+                entry_name: "index",
+                entry: &entry,
+                modules: std::slice::from_ref(&entry),
+                module_graph: &module_graph,
+            },
+            &context,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].name, "index.html");
+    assert_eq!(resources[0].resource_type, ResourceType::Html);
+    assert_eq!(
+        String::from_utf8(resources[0].bytes.clone()).unwrap(),
+        "<!doctype html><link rel=stylesheet href=https://a.com/remote.css><link rel=stylesheet href=/assets/index.css><body><script src=/assets/index.js></script>"
+    );
+}
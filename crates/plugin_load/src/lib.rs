@@ -0,0 +1,81 @@
+use std::{path::Path, sync::Arc};
+
+use async_trait::async_trait;
+use toy_farm_core::{
+    error::Result, CompilationContext, CompilationError, ModuleType, Plugin, PluginHookContext,
+    PluginLoadHookParam, PluginLoadHookResult, VIRTUAL_MODULE_PREFIX,
+};
+use toy_farm_toolkit::fs::module_type_from_path;
+use toy_farm_utils::base64_encode;
+
+/// Built-in load plugin, read the resolved path from the disk and infer the module type from its extension.
+/// Files with asset extensions and binary files are treated as [ModuleType::Asset], the content of an asset is always base64 encoded.
+pub struct FarmPluginLoad {}
+
+impl FarmPluginLoad {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for FarmPluginLoad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for FarmPluginLoad {
+    fn name(&self) -> &str {
+        "FarmPluginLoad"
+    }
+
+    /// load after custom plugins, so that they have a chance to load the module first
+    fn priority(&self) -> i32 {
+        99
+    }
+
+    async fn load(
+        &self,
+        param: Arc<PluginLoadHookParam>,
+        _context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginLoadHookResult>> {
+        // virtual modules should be loaded by the plugins who create them
+        if param.resolved_path.starts_with(VIRTUAL_MODULE_PREFIX) {
+            return Ok(None);
+        }
+
+        let path = Path::new(&param.resolved_path);
+
+        if !tokio::fs::metadata(path)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            return Ok(None);
+        }
+
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| CompilationError::LoadError {
+                resolved_path: param.resolved_path.clone(),
+                source: Some(Box::new(e)),
+            })?;
+        let module_type = module_type_from_path(&param.resolved_path).unwrap_or_default();
+
+        let (content, module_type) = if module_type == ModuleType::Asset {
+            (base64_encode(&bytes), module_type)
+        } else {
+            match String::from_utf8(bytes) {
+                Ok(content) => (content, module_type),
+                Err(e) => (base64_encode(e.as_bytes()), ModuleType::Asset),
+            }
+        };
+
+        Ok(Some(PluginLoadHookResult {
+            content,
+            module_type,
+            source_map: None,
+        }))
+    }
+}
@@ -0,0 +1,65 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use toy_farm_core::{CompilationContext, Config, ModuleType, Plugin, PluginLoadHookParam};
+use toy_farm_plugin_load::FarmPluginLoad;
+use toy_farm_utils::base64_encode;
+
+fn fixture_path(name: &str) -> String {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/load")
+        .join(name)
+        .to_string_lossy()
+        .to_string()
+}
+
+async fn load(resolved_path: String) -> Option<(String, ModuleType)> {
+    let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+    let param = PluginLoadHookParam {
+        module_id: resolved_path.clone(),
+        resolved_path,
+        query: vec![],
+        meta: HashMap::new(),
+    };
+
+    FarmPluginLoad::new()
+        .load(Arc::new(param), context, Default::default())
+        .await
+        .unwrap()
+        .map(|result| (result.content, result.module_type))
+}
+
+#[tokio::test]
+async fn load_script() {
+    let (content, module_type) = load(fixture_path("index.ts")).await.unwrap();
+
+    assert_eq!(content, "export const a = 1;\n");
+    assert_eq!(module_type, ModuleType::Ts);
+}
+
+#[tokio::test]
+async fn load_binary_as_asset() {
+    let (content, module_type) = load(fixture_path("logo.png")).await.unwrap();
+    assert_eq!(module_type, ModuleType::Asset);
+    assert_eq!(
+        content,
+        base64_encode(&std::fs::read(fixture_path("logo.png")).unwrap())
+    );
+
+    // assets are base64 encoded even if their content is valid utf8
+    let (content, module_type) = load(fixture_path("icon.svg")).await.unwrap();
+    assert_eq!(module_type, ModuleType::Asset);
+    assert_eq!(
+        content,
+        base64_encode(&std::fs::read(fixture_path("icon.svg")).unwrap())
+    );
+
+    // the extension does not matter if the content is not valid utf8
+    let (_, module_type) = load(fixture_path("binary.ts")).await.unwrap();
+    assert_eq!(module_type, ModuleType::Asset);
+}
+
+#[tokio::test]
+async fn skip_virtual_and_missing_modules() {
+    assert!(load("virtual:entry".to_string()).await.is_none());
+    assert!(load(fixture_path("missing.ts")).await.is_none());
+}
@@ -0,0 +1,87 @@
+//! Aliases configured by `ResolveConfig.alias`, the keys are matched in three ways:
+//! * `key$` matches the source `key` exactly.
+//! * `$__farm_regex:pattern` matches the source with the regex, captures can be referenced in the target like `$1`.
+//! * otherwise the key is a prefix, `key` matches `key` and `key/...`, a key ending with `/` matches any source starting with it.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use toy_farm_core::{error::Result, CompilationError};
+
+pub const REGEX_ALIAS_PREFIX: &str = "$__farm_regex:";
+
+enum AliasPattern {
+    Exact(String),
+    Prefix(String),
+    Regex(Regex),
+}
+
+struct Alias {
+    key: String,
+    pattern: AliasPattern,
+    target: String,
+}
+
+pub struct Aliases {
+    aliases: Vec<Alias>,
+}
+
+impl Aliases {
+    /// Exact keys are matched first, then the other keys with the longest key first.
+    /// An invalid regex key is reported as [CompilationError::GenericError] naming the key.
+    pub fn new(alias: &HashMap<String, String>) -> Result<Self> {
+        let mut aliases = alias
+            .iter()
+            .map(|(key, target)| {
+                let pattern = if let Some(regex) = key.strip_prefix(REGEX_ALIAS_PREFIX) {
+                    AliasPattern::Regex(Regex::new(regex).map_err(|e| {
+                        CompilationError::GenericError(format!(
+                            "Invalid regex of alias `{}`: {}",
+                            key, e
+                        ))
+                    })?)
+                } else if let Some(exact) = key.strip_suffix('$') {
+                    AliasPattern::Exact(exact.to_string())
+                } else {
+                    AliasPattern::Prefix(key.clone())
+                };
+
+                Ok(Alias {
+                    key: key.clone(),
+                    pattern,
+                    target: target.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        aliases.sort_by(|a, b| {
+            let is_exact = |alias: &Alias| matches!(alias.pattern, AliasPattern::Exact(_));
+
+            is_exact(b)
+                .cmp(&is_exact(a))
+                .then_with(|| b.key.len().cmp(&a.key.len()))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        Ok(Self { aliases })
+    }
+
+    /// Replace the matched part of `source` with the target of the first matched alias.
+    pub fn apply(&self, source: &str) -> Option<String> {
+        self.aliases.iter().find_map(|alias| match &alias.pattern {
+            AliasPattern::Exact(key) => (source == key).then(|| alias.target.clone()),
+            AliasPattern::Prefix(key) => {
+                let rest = source.strip_prefix(key.as_str())?;
+
+                if key.ends_with('/') || rest.is_empty() || rest.starts_with('/') {
+                    Some(format!("{}{}", alias.target, rest))
+                } else {
+                    None
+                }
+            }
+            AliasPattern::Regex(regex) => regex
+                .is_match(source)
+                .then(|| regex.replace(source, alias.target.as_str()).into_owned()),
+        })
+    }
+}
@@ -0,0 +1,239 @@
+//! Node compatible resolution of the `exports` and `imports` fields of package.json,
+//! see https://nodejs.org/api/packages.html#resolution-algorithm-specification.
+
+use std::{borrow::Cow, path::PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::package_json::PackageJson;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackageExportsError {
+    #[error("Package subpath `{subpath}` is not defined by \"exports\" in {package_json}")]
+    NotExported {
+        subpath: String,
+        package_json: String,
+    },
+    #[error("Package subpath `{subpath}` is defined in {package_json}, but none of its conditions match {conditions:?}")]
+    NoMatchedConditions {
+        subpath: String,
+        package_json: String,
+        conditions: Vec<String>,
+    },
+    #[error(
+        "Package import specifier `{specifier}` is not defined by \"imports\" in {package_json}"
+    )]
+    ImportNotDefined {
+        specifier: String,
+        package_json: String,
+    },
+    #[error("Invalid target `{target}` defined for `{subpath}` in {package_json}, targets must start with \"./\" and must not contain `.`, `..` or `node_modules` segments")]
+    InvalidTarget {
+        target: String,
+        subpath: String,
+        package_json: String,
+    },
+    #[error("Invalid \"exports\" in {package_json}, an object can not contain both subpath keys starting with \".\" and condition keys")]
+    InvalidExports { package_json: String },
+}
+
+/// Resolved target of the `exports` or `imports` field.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PackageTarget {
+    /// a file in the package
+    Path(PathBuf),
+    /// a bare specifier that should be resolved from the package, only `imports` can map to another package,
+    /// e.g. `"#dep": "lodash"`
+    Package(String),
+}
+
+/// Resolve `subpath` of the package through its `exports` field, `subpath` is `.` for the main entry or `./` followed by the path.
+pub fn resolve_exports(
+    package_json: &PackageJson,
+    subpath: &str,
+    conditions: &[String],
+) -> Result<PathBuf, PackageExportsError> {
+    let package_json_path = package_json.path().to_string_lossy().to_string();
+    let exports = package_json.exports().unwrap_or(&Value::Null);
+
+    let exports_map = match exports {
+        Value::Object(map) if map.keys().all(|key| key.starts_with('.')) => Cow::Borrowed(map),
+        Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => {
+            return Err(PackageExportsError::InvalidExports {
+                package_json: package_json_path,
+            });
+        }
+        // conditions or a single target of the main entry
+        _ => Cow::Owned(Map::from_iter([(".".to_string(), exports.clone())])),
+    };
+
+    let not_exported = || PackageExportsError::NotExported {
+        subpath: subpath.to_string(),
+        package_json: package_json_path.clone(),
+    };
+
+    let (target, pattern_match) = match_subpath(&exports_map, subpath).ok_or_else(not_exported)?;
+
+    match resolve_target(target, pattern_match, conditions, false) {
+        Ok(Some(PackageTarget::Path(path))) => Ok(package_json.dir.join(path)),
+        Ok(Some(PackageTarget::Package(target))) | Err(target) => {
+            Err(PackageExportsError::InvalidTarget {
+                target,
+                subpath: subpath.to_string(),
+                package_json: package_json_path,
+            })
+        }
+        // `null` excludes the subpath explicitly
+        Ok(None) if target.is_null() => Err(not_exported()),
+        Ok(None) => Err(PackageExportsError::NoMatchedConditions {
+            subpath: subpath.to_string(),
+            package_json: package_json_path,
+            conditions: conditions.to_vec(),
+        }),
+    }
+}
+
+/// Resolve `specifier` starting with `#` through the `imports` field of the package.
+pub fn resolve_imports(
+    package_json: &PackageJson,
+    specifier: &str,
+    conditions: &[String],
+) -> Result<PackageTarget, PackageExportsError> {
+    let package_json_path = package_json.path().to_string_lossy().to_string();
+    let not_defined = || PackageExportsError::ImportNotDefined {
+        specifier: specifier.to_string(),
+        package_json: package_json_path.clone(),
+    };
+
+    if specifier == "#" || specifier.starts_with("#/") {
+        return Err(not_defined());
+    }
+
+    let Some(Value::Object(imports)) = package_json.imports() else {
+        return Err(not_defined());
+    };
+    let (target, pattern_match) = match_subpath(imports, specifier).ok_or_else(not_defined)?;
+
+    match resolve_target(target, pattern_match, conditions, true) {
+        Ok(Some(PackageTarget::Path(path))) => Ok(PackageTarget::Path(package_json.dir.join(path))),
+        Ok(Some(target)) => Ok(target),
+        Ok(None) if target.is_null() => Err(not_defined()),
+        Ok(None) => Err(PackageExportsError::NoMatchedConditions {
+            subpath: specifier.to_string(),
+            package_json: package_json_path,
+            conditions: conditions.to_vec(),
+        }),
+        Err(target) => Err(PackageExportsError::InvalidTarget {
+            target,
+            subpath: specifier.to_string(),
+            package_json: package_json_path,
+        }),
+    }
+}
+
+/// Find the target of `key` in the map, exact keys win over the patterns containing a single `*`.
+/// Among the matched patterns, the one with the longest prefix wins. Return the target and the part matched by `*`.
+fn match_subpath<'a, 'b>(
+    map: &'a Map<String, Value>,
+    key: &'b str,
+) -> Option<(&'a Value, Option<&'b str>)> {
+    if let Some(target) = map.get(key).filter(|_| !key.contains('*')) {
+        return Some((target, None));
+    }
+
+    let mut best_match: Option<(&str, &Value, &str)> = None;
+
+    for (pattern, target) in map {
+        let Some((base, trailer)) = pattern.split_once('*') else {
+            continue;
+        };
+
+        if trailer.contains('*')
+            || !key.starts_with(base)
+            || key == base
+            || !key.ends_with(trailer)
+            || key.len() < pattern.len()
+        {
+            continue;
+        }
+
+        let is_better = best_match.is_none_or(|(best, _, _)| {
+            (base.len(), pattern.len()) > (best.find('*').unwrap(), best.len())
+        });
+
+        if is_better {
+            best_match = Some((pattern, target, &key[base.len()..key.len() - trailer.len()]));
+        }
+    }
+
+    best_match.map(|(_, target, pattern_match)| (target, Some(pattern_match)))
+}
+
+/// Resolve the target with the conditions, return the invalid target string as the error.
+fn resolve_target(
+    target: &Value,
+    pattern_match: Option<&str>,
+    conditions: &[String],
+    is_imports: bool,
+) -> Result<Option<PackageTarget>, String> {
+    match target {
+        Value::String(target) => {
+            let resolved = match pattern_match {
+                Some(pattern_match) => target.replace('*', pattern_match),
+                None => target.clone(),
+            };
+
+            if let Some(path) = resolved.strip_prefix("./") {
+                let is_valid = path.split(['/', '\\']).all(|segment| {
+                    segment != "."
+                        && segment != ".."
+                        && !segment.eq_ignore_ascii_case("node_modules")
+                });
+
+                return if is_valid {
+                    Ok(Some(PackageTarget::Path(PathBuf::from(path))))
+                } else {
+                    Err(target.clone())
+                };
+            }
+
+            let is_bare = !resolved.starts_with('/')
+                && !resolved.starts_with("../")
+                && !resolved.contains("://");
+
+            if is_imports && is_bare {
+                Ok(Some(PackageTarget::Package(resolved)))
+            } else {
+                Err(target.clone())
+            }
+        }
+        Value::Array(targets) => {
+            let mut last_error = None;
+
+            for target in targets {
+                match resolve_target(target, pattern_match, conditions, is_imports) {
+                    Ok(Some(resolved)) => return Ok(Some(resolved)),
+                    Ok(None) => continue,
+                    Err(e) => last_error = Some(e),
+                }
+            }
+
+            last_error.map_or(Ok(None), Err)
+        }
+        Value::Object(conditional_targets) => {
+            for (condition, target) in conditional_targets {
+                if condition == "default" || conditions.contains(condition) {
+                    if let Some(resolved) =
+                        resolve_target(target, pattern_match, conditions, is_imports)?
+                    {
+                        return Ok(Some(resolved));
+                    }
+                }
+            }
+
+            Ok(None)
+        }
+        Value::Null => Ok(None),
+        target => Err(target.to_string()),
+    }
+}
@@ -1,29 +1,82 @@
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, RwLock as StdRwLock},
     vec,
 };
 
 use async_trait::async_trait;
+use serde_json::Value;
 use tokio::sync::RwLock;
 use toy_farm_core::{
-    error::Result, external::ExternalConfig, CompilationContext, Config, Plugin,
-    PluginResolveHookParam, PluginResolveHookResult,
+    error::Result, external::ExternalConfig, CompilationContext, CompilationError, Config,
+    ModuleId, Plugin, PluginHookContext, PluginResolveHookParam, PluginResolveHookResult,
+    PluginUpdateModulesHookParam, ResolveConfig, ResolveKind, VIRTUAL_MODULE_PREFIX,
 };
+use toy_farm_utils::canonicalize_path;
 
-pub struct FarmPluginResolve {
+use alias::Aliases;
+use exports::{resolve_exports, resolve_imports, PackageExportsError, PackageTarget};
+use package_json::PackageJsonCache;
+use tsconfig::TsconfigCache;
+
+pub mod alias;
+pub mod exports;
+pub mod package_json;
+pub mod tsconfig;
+
+/// The root, the resolve config and the aliases the sources are resolved with
+struct ResolveOptions {
     root: String,
+    resolve_config: ResolveConfig,
+    aliases: Aliases,
+}
+
+impl ResolveOptions {
+    fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            root: config.root.clone(),
+            resolve_config: config.resolve.clone(),
+            aliases: Aliases::new(&config.resolve.alias)?,
+        })
+    }
+}
+
+pub struct FarmPluginResolve {
+    /// replaced in `config_resolved`, so that the config patched by the `config` hooks of plugins takes effect
+    options: StdRwLock<Arc<ResolveOptions>>,
     // resolver: Resolver,
     external_config: RwLock<Option<ExternalConfig>>,
+    package_json_cache: PackageJsonCache,
+    tsconfig_cache: TsconfigCache,
 }
 impl FarmPluginResolve {
-    pub fn new(config: &Config) -> Self {
-        Self {
-            root: config.root.clone(),
+    /// An invalid alias is reported here, the options are rebuilt in `config_resolved` with the patched config.
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            options: StdRwLock::new(Arc::new(ResolveOptions::new(config)?)),
             // resolver: Resolver::new(),
             external_config: RwLock::new(None),
-        }
+            package_json_cache: PackageJsonCache::default(),
+            tsconfig_cache: TsconfigCache::default(),
+        })
+    }
+
+    fn options(&self) -> Arc<ResolveOptions> {
+        self.options.read().unwrap().clone()
+    }
+
+    /// conditions of `exports` and `imports`, `import` or `require` is added according to the kind
+    fn conditions(&self, kind: &ResolveKind) -> Vec<String> {
+        let mut conditions = self.options().resolve_config.conditions.clone();
+        let condition = if *kind == ResolveKind::Require {
+            "require"
+        } else {
+            "import"
+        };
+        conditions.push(condition.to_string());
+
+        conditions
     }
 
     async fn is_external(&self, source: &str) -> bool {
@@ -33,41 +86,242 @@ impl FarmPluginResolve {
             false
         }
     }
-    async fn try_alias(&self, source: &str) -> Option<String> {
-        let alias = [("@/", "src/"), ("@", "src/"), ("~", "node_modules/")];
-        for (key, value) in alias.iter() {
-            if source.starts_with(key) {
-                return Some(source.replacen(key, value, 1));
-            }
+    /// Resolve the aliased source from the root, as a path first and then as a package.
+    async fn try_alias(
+        &self,
+        aliased: &str,
+        kind: &ResolveKind,
+    ) -> std::result::Result<Option<String>, PackageExportsError> {
+        let options = self.options();
+        let root = Path::new(&options.root);
+
+        if let Some(resolved_path) = self
+            .try_relative_or_absolute_path(aliased, root, kind)
+            .await
+        {
+            return Ok(Some(resolved_path));
+        }
+
+        if aliased.starts_with('.') || Path::new(aliased).is_absolute() {
+            return Ok(None);
         }
-        None
+
+        self.try_node_modules(aliased, root, kind)
     }
 
-    async fn try_relative_or_absolute_path(&self, source: &str, base_dir: &Path) -> Option<String> {
+    async fn try_relative_or_absolute_path(
+        &self,
+        source: &str,
+        base_dir: &Path,
+        kind: &ResolveKind,
+    ) -> Option<String> {
         let path = if Path::new(source).is_absolute() {
             PathBuf::from(source)
         } else {
             base_dir.join(source)
         };
 
-        if path.exists() {
-            Some(path.to_string_lossy().into_owned())
-        } else {
-            None
+        self.try_file_or_directory(&path, kind)
+            .map(|path| path_to_string(&path))
+    }
+
+    /// Resolve `path` as a file first, then as a directory. Only files are returned.
+    fn try_file_or_directory(&self, path: &Path, kind: &ResolveKind) -> Option<PathBuf> {
+        self.try_file(path)
+            .or_else(|| self.try_directory(path, kind))
+    }
+
+    /// `path` itself if it is a file, otherwise `path` with the first of `extensions` that exists appended,
+    /// e.g. `./dep` is resolved to `./dep.ts`.
+    fn try_file(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_file() {
+            return Some(path.to_path_buf());
         }
+
+        let file_name = path.file_name()?.to_string_lossy();
+
+        self.options()
+            .resolve_config
+            .extensions
+            .iter()
+            .map(|ext| path.with_file_name(format!("{}.{}", file_name, ext)))
+            .find(|path| path.is_file())
     }
 
-    async fn try_node_modules(&self, source: &str, base_dir: &Path) -> Option<String> {
-        let mut current = base_dir.to_path_buf();
-        while let Some(parent) = current.parent() {
-            let node_modules = current.join("node_modules").join(source);
-            if node_modules.exists() {
-                return Some(node_modules.to_string_lossy().into_owned());
+    /// Resolve the entry of a directory, the fields of its package.json are tried in the order of `main_fields`,
+    /// then the `main_files` of the directory are tried.
+    fn try_directory(&self, dir: &Path, kind: &ResolveKind) -> Option<PathBuf> {
+        if !dir.is_dir() {
+            return None;
+        }
+
+        if let Some(package_json) = self.package_json_cache.load(dir) {
+            for field in &self.options().resolve_config.main_fields {
+                let entry = if field == "exports" {
+                    if package_json.exports().is_none() {
+                        continue;
+                    }
+
+                    resolve_exports(&package_json, ".", &self.conditions(kind))
+                        .ok()
+                        .filter(|path| path.is_file())
+                } else {
+                    // fields that are not strings are ignored, e.g. `browser` with an object value
+                    match package_json.field(field) {
+                        Some(Value::String(entry)) => {
+                            let entry = dir.join(entry);
+                            // the entry is not resolved as a package again to avoid loops like `"main": "."`
+                            self.try_file(&entry)
+                                .or_else(|| self.try_main_files(&entry))
+                        }
+                        _ => None,
+                    }
+                };
+
+                if entry.is_some() {
+                    return entry;
+                }
             }
-            current = parent.to_path_buf();
         }
-        None
+
+        self.try_main_files(dir)
     }
+
+    /// try `main_files` of `dir` with `extensions`, e.g. `dir/index.ts`
+    fn try_main_files(&self, dir: &Path) -> Option<PathBuf> {
+        if !dir.is_dir() {
+            return None;
+        }
+
+        self.options()
+            .resolve_config
+            .main_files
+            .iter()
+            .find_map(|main_file| self.try_file(&dir.join(main_file)))
+    }
+
+    /// Resolve bare specifiers through `paths` and `baseUrl` of the closest tsconfig.json of the importer.
+    /// The importer watches the tsconfig files in the [toy_farm_core::watch_graph::WatchGraph], so that it is rebuilt when they change.
+    async fn try_tsconfig_paths(
+        &self,
+        source: &str,
+        base_dir: &Path,
+        importer: Option<&ModuleId>,
+        kind: &ResolveKind,
+        context: &Arc<CompilationContext>,
+    ) -> Result<Option<String>> {
+        if source.starts_with('.')
+            || Path::new(source).is_absolute()
+            || base_dir
+                .components()
+                .any(|component| component.as_os_str() == "node_modules")
+        {
+            return Ok(None);
+        }
+
+        let Some(tsconfig) = self.tsconfig_cache.load_closest(base_dir) else {
+            return Ok(None);
+        };
+
+        if let Some(importer) = importer {
+            let mut watch_graph = context.watch_graph.write().await;
+            watch_graph.add_node(importer.clone());
+
+            for file in &tsconfig.files {
+                let dep = ModuleId::new(&path_to_string(file), "");
+                watch_graph.add_node(dep.clone());
+                watch_graph.add_edge(importer, &dep)?;
+            }
+        }
+
+        let resolved_path = tsconfig
+            .candidates(source)
+            .into_iter()
+            .chain(
+                tsconfig
+                    .base_url
+                    .as_ref()
+                    .map(|base_url| base_url.join(source)),
+            )
+            .find_map(|candidate| self.try_file_or_directory(&candidate, kind));
+
+        Ok(resolved_path.map(|path| path_to_string(&path)))
+    }
+
+    /// Resolve the package from the closest `node_modules`, through the `exports` field of the package if it exists.
+    /// Subpaths not exported are resolved from the files of the package unless `strict_exports` is enabled.
+    fn try_node_modules(
+        &self,
+        source: &str,
+        base_dir: &Path,
+        kind: &ResolveKind,
+    ) -> std::result::Result<Option<String>, PackageExportsError> {
+        let (package_name, subpath) = split_package_specifier(source);
+
+        for dir in base_dir.ancestors() {
+            let package_dir = dir.join("node_modules").join(package_name);
+
+            if !package_dir.exists() {
+                continue;
+            }
+
+            if let Some(package_json) = self
+                .package_json_cache
+                .load(&package_dir)
+                .filter(|package_json| package_json.exports().is_some())
+            {
+                match resolve_exports(&package_json, &subpath, &self.conditions(kind)) {
+                    Ok(path) => return Ok(path.is_file().then(|| path_to_string(&path))),
+                    Err(e) if self.options().resolve_config.strict_exports => return Err(e),
+                    Err(_) => {}
+                }
+            }
+
+            let path = dir.join("node_modules").join(source);
+            return Ok(self
+                .try_file_or_directory(&path, kind)
+                .map(|path| path_to_string(&path)));
+        }
+
+        Ok(None)
+    }
+
+    /// Resolve `#internal` specifiers through the `imports` field of the closest package.json.
+    fn try_imports(
+        &self,
+        source: &str,
+        base_dir: &Path,
+        kind: &ResolveKind,
+    ) -> std::result::Result<Option<String>, PackageExportsError> {
+        let Some(package_json) = self.package_json_cache.load_closest(base_dir) else {
+            return Ok(None);
+        };
+
+        match resolve_imports(&package_json, source, &self.conditions(kind))? {
+            PackageTarget::Path(path) => Ok(path.is_file().then(|| path_to_string(&path))),
+            PackageTarget::Package(specifier) => {
+                self.try_node_modules(&specifier, &package_json.dir, kind)
+            }
+        }
+    }
+}
+
+/// split `@scope/pkg/sub` into `@scope/pkg` and `./sub`, the subpath is `.` if the package itself is imported
+fn split_package_specifier(source: &str) -> (&str, String) {
+    let separator = if source.starts_with('@') {
+        source.match_indices('/').nth(1).map(|(index, _)| index)
+    } else {
+        source.find('/')
+    };
+
+    match separator {
+        Some(index) => (&source[..index], format!(".{}", &source[index..])),
+        None => (source, ".".to_string()),
+    }
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
 }
 #[async_trait]
 impl Plugin for FarmPluginResolve {
@@ -75,18 +329,28 @@ impl Plugin for FarmPluginResolve {
         "FarmPluginResolve"
     }
 
+    async fn config_resolved(&self, config: &Config) -> Result<Option<()>> {
+        *self.options.write().unwrap() = Arc::new(ResolveOptions::new(config)?);
+
+        Ok(Some(()))
+    }
+
     async fn resolve(
         &self,
         param: Arc<PluginResolveHookParam>,
         context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
     ) -> Result<Option<PluginResolveHookResult>> {
-        let base_dir = if let Some(importer) = &param.importer {
-            Path::new(&importer.resolved_path(&context.config.root))
-                .parent()
-                .unwrap()
-                .to_path_buf()
-        } else {
-            PathBuf::from(&self.root)
+        let options = self.options();
+        // virtual modules do not have a directory, relative sources of them are resolved from the root
+        let base_dir = match &param.importer {
+            Some(importer) if !importer.relative_path().starts_with(VIRTUAL_MODULE_PREFIX) => {
+                Path::new(&importer.resolved_path(&context.config.root))
+                    .parent()
+                    .unwrap()
+                    .to_path_buf()
+            }
+            _ => PathBuf::from(&options.root),
         };
 
         // Check if it's external
@@ -100,22 +364,65 @@ impl Plugin for FarmPluginResolve {
             }));
         }
 
-        // Try resolving in order: alias, relative/absolute path, node_modules
-
-        let resolved_path = self.try_alias(&param.source).await;
-        let resolved_path = match resolved_path {
-            Some(path) => Some(path),
-            None => {
-                self.try_relative_or_absolute_path(&param.source, &base_dir)
-                    .await
-            }
+        let to_resolve_error = |e: PackageExportsError| CompilationError::ResolveError {
+            importer: param
+                .importer
+                .as_ref()
+                .map(|importer| importer.to_string())
+                .unwrap_or_else(|| options.root.clone()),
+            src: param.source.clone(),
+            source: Some(Box::new(e)),
         };
-        let resolved_path = match resolved_path {
-            Some(path) => Some(path),
-            None => self.try_node_modules(&param.source, &base_dir).await,
+
+        if param.source.starts_with('#') {
+            let resolved_path = self
+                .try_imports(&param.source, &base_dir, &param.kind)
+                .map_err(to_resolve_error)?;
+
+            return Ok(resolved_path.map(|resolved_path| PluginResolveHookResult {
+                resolved_path: path_to_string(&canonicalize_path(
+                    Path::new(&resolved_path),
+                    options.resolve_config.symlinks,
+                )),
+                ..Default::default()
+            }));
+        }
+
+        // Try resolving in order: alias, relative/absolute path, tsconfig paths, node_modules.
+        // The aliased source is resolved from the root and is not resolved as the original source again
+
+        let resolved_path = if let Some(aliased) = options.aliases.apply(&param.source) {
+            self.try_alias(&aliased, &param.kind)
+                .await
+                .map_err(to_resolve_error)?
+        } else if let Some(path) = self
+            .try_relative_or_absolute_path(&param.source, &base_dir, &param.kind)
+            .await
+        {
+            Some(path)
+        } else if let Some(path) = self
+            .try_tsconfig_paths(
+                &param.source,
+                &base_dir,
+                param.importer.as_ref(),
+                &param.kind,
+                &context,
+            )
+            .await?
+        {
+            Some(path)
+        } else {
+            self.try_node_modules(&param.source, &base_dir, &param.kind)
+                .map_err(to_resolve_error)?
         };
 
         if let Some(resolved_path) = resolved_path {
+            // the same file reached through different paths must have the same module id
+            let resolved_path = path_to_string(&canonicalize_path(
+                Path::new(&resolved_path),
+                options.resolve_config.symlinks,
+            ));
+
             return Ok(Some(PluginResolveHookResult {
                 resolved_path,
                 external: false,
@@ -123,7 +430,7 @@ impl Plugin for FarmPluginResolve {
                 query: vec![],      // You might want to parse query here
                 meta: HashMap::new(),
             }));
-        } else if context.config.resolve.auto_external_failed_resolve {
+        } else if options.resolve_config.auto_external_failed_resolve {
             return Ok(Some(PluginResolveHookResult {
                 resolved_path: param.source.clone(),
                 external: true,
@@ -135,4 +442,23 @@ impl Plugin for FarmPluginResolve {
 
         Ok(None)
     }
+
+    /// tsconfig files are not modules, invalidate the cached tsconfigs when they change
+    async fn update_modules(
+        &self,
+        param: &mut PluginUpdateModulesHookParam,
+        _context: &Arc<CompilationContext>,
+    ) -> Result<Option<()>> {
+        let options = self.options();
+
+        for (path, update_type) in &param.paths {
+            let path = canonicalize_path(
+                &Path::new(&options.root).join(path),
+                options.resolve_config.symlinks,
+            );
+            self.tsconfig_cache.invalidate(&path, update_type);
+        }
+
+        Ok(None)
+    }
 }
@@ -0,0 +1,69 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde_json::Value;
+
+#[derive(Debug)]
+pub struct PackageJson {
+    /// the directory containing the package.json
+    pub dir: PathBuf,
+    pub raw: Value,
+}
+
+impl PackageJson {
+    pub fn path(&self) -> PathBuf {
+        self.dir.join("package.json")
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.raw.get(name)
+    }
+
+    pub fn exports(&self) -> Option<&Value> {
+        self.field("exports")
+    }
+
+    pub fn imports(&self) -> Option<&Value> {
+        self.field("imports")
+    }
+}
+
+/// Parsed package.json files keyed by their directories, a missing or malformed package.json is cached as [None].
+#[derive(Default)]
+pub struct PackageJsonCache {
+    cache: Mutex<HashMap<PathBuf, Option<Arc<PackageJson>>>>,
+}
+
+impl PackageJsonCache {
+    /// load the package.json in `dir`
+    pub fn load(&self, dir: &Path) -> Option<Arc<PackageJson>> {
+        if let Some(package_json) = self.cache.lock().unwrap().get(dir) {
+            return package_json.clone();
+        }
+
+        let package_json = std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .map(|raw| {
+                Arc::new(PackageJson {
+                    dir: dir.to_path_buf(),
+                    raw,
+                })
+            });
+
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(dir.to_path_buf(), package_json.clone());
+
+        package_json
+    }
+
+    /// load the closest package.json from `dir` upwards
+    pub fn load_closest(&self, dir: &Path) -> Option<Arc<PackageJson>> {
+        dir.ancestors().find_map(|dir| self.load(dir))
+    }
+}
@@ -0,0 +1,333 @@
+//! `compilerOptions.baseUrl` and `compilerOptions.paths` of tsconfig.json, `extends` chains are followed.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde_json::Value;
+use toy_farm_core::UpdateType;
+use toy_farm_utils::normalize_path;
+
+pub const TSCONFIG_FILE_NAME: &str = "tsconfig.json";
+
+/// patterns of `paths` and their targets, in the order of the keys
+pub type TsconfigPaths = Vec<(String, Vec<String>)>;
+
+#[derive(Debug, Default)]
+pub struct Tsconfig {
+    /// the tsconfig.json and the files it extends, a change of any of them invalidates the tsconfig
+    pub files: Vec<PathBuf>,
+    pub base_url: Option<PathBuf>,
+    pub paths: TsconfigPaths,
+    /// targets of `paths` are relative to `baseUrl` if it is set, otherwise relative to the tsconfig defining `paths`
+    pub paths_base: PathBuf,
+}
+
+impl Tsconfig {
+    /// Candidate paths of `specifier` mapped by `paths`. An exact pattern wins over the patterns containing `*`,
+    /// among which the one with the longest prefix wins.
+    pub fn candidates(&self, specifier: &str) -> Vec<PathBuf> {
+        let to_candidates = |targets: &[String], matched: &str| {
+            targets
+                .iter()
+                .map(|target| {
+                    normalize_path(&self.paths_base.join(target.replacen('*', matched, 1)))
+                })
+                .collect()
+        };
+
+        if let Some((_, targets)) = self
+            .paths
+            .iter()
+            .find(|(pattern, _)| !pattern.contains('*') && pattern == specifier)
+        {
+            return to_candidates(targets, "");
+        }
+
+        let mut best_match: Option<(usize, &[String], &str)> = None;
+
+        for (pattern, targets) in &self.paths {
+            let Some((prefix, suffix)) = pattern.split_once('*') else {
+                continue;
+            };
+
+            if specifier.len() < prefix.len() + suffix.len()
+                || !specifier.starts_with(prefix)
+                || !specifier.ends_with(suffix)
+            {
+                continue;
+            }
+
+            if best_match.is_none_or(|(len, _, _)| prefix.len() > len) {
+                let matched = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                best_match = Some((prefix.len(), targets, matched));
+            }
+        }
+
+        best_match
+            .map(|(_, targets, matched)| to_candidates(targets, matched))
+            .unwrap_or_default()
+    }
+}
+
+/// Parsed tsconfig files keyed by their paths, and the closest tsconfig.json of the directories.
+#[derive(Default)]
+pub struct TsconfigCache {
+    tsconfigs: Mutex<HashMap<PathBuf, Option<Arc<Tsconfig>>>>,
+    closest: Mutex<HashMap<PathBuf, Option<PathBuf>>>,
+}
+
+impl TsconfigCache {
+    /// load the closest tsconfig.json from `dir` upwards
+    pub fn load_closest(&self, dir: &Path) -> Option<Arc<Tsconfig>> {
+        let cached = self.closest.lock().unwrap().get(dir).cloned();
+        let path = match cached {
+            Some(path) => path,
+            None => {
+                let path = dir
+                    .ancestors()
+                    .map(|dir| dir.join(TSCONFIG_FILE_NAME))
+                    .find(|path| path.is_file());
+                self.closest
+                    .lock()
+                    .unwrap()
+                    .insert(dir.to_path_buf(), path.clone());
+                path
+            }
+        };
+
+        self.load(&path?)
+    }
+
+    /// load the tsconfig at `path`, a missing or malformed tsconfig is cached as [None]
+    pub fn load(&self, path: &Path) -> Option<Arc<Tsconfig>> {
+        if let Some(tsconfig) = self.tsconfigs.lock().unwrap().get(path) {
+            return tsconfig.clone();
+        }
+
+        let mut files = vec![];
+        let tsconfig = parse_tsconfig(path, &mut files).map(|options| {
+            let paths_base = options
+                .base_url
+                .clone()
+                .or(options.paths.as_ref().map(|(_, dir)| dir.clone()))
+                .unwrap_or_default();
+
+            Arc::new(Tsconfig {
+                files,
+                base_url: options.base_url,
+                paths: options.paths.map(|(paths, _)| paths).unwrap_or_default(),
+                paths_base,
+            })
+        });
+
+        self.tsconfigs
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), tsconfig.clone());
+
+        tsconfig
+    }
+
+    /// Invalidate the tsconfigs that contain the changed file, return true if any is invalidated.
+    /// The closest tsconfig.json of the directories is looked up again if a tsconfig.json is added or removed.
+    pub fn invalidate(&self, path: &Path, update_type: &UpdateType) -> bool {
+        let mut invalidated = false;
+
+        if *update_type != UpdateType::Updated
+            && path
+                .file_name()
+                .is_some_and(|name| name == TSCONFIG_FILE_NAME)
+        {
+            let mut closest = self.closest.lock().unwrap();
+            invalidated = !closest.is_empty();
+            closest.clear();
+        }
+
+        self.tsconfigs
+            .lock()
+            .unwrap()
+            .retain(|tsconfig_path, tsconfig| {
+                let contains = tsconfig_path == path
+                    || tsconfig
+                        .as_ref()
+                        .is_some_and(|tsconfig| tsconfig.files.iter().any(|file| file == path));
+                invalidated |= contains;
+                !contains
+            });
+
+        invalidated
+    }
+}
+
+#[derive(Default)]
+struct CompilerOptions {
+    base_url: Option<PathBuf>,
+    /// `paths` and the directory of the tsconfig defining it
+    paths: Option<(TsconfigPaths, PathBuf)>,
+}
+
+/// Parse the tsconfig at `path` and the tsconfigs it extends, `files` collects the parsed files to detect cycles.
+fn parse_tsconfig(path: &Path, files: &mut Vec<PathBuf>) -> Option<CompilerOptions> {
+    if files.iter().any(|file| file == path) {
+        return None;
+    }
+    files.push(path.to_path_buf());
+
+    let content = std::fs::read_to_string(path).ok()?;
+    let raw: Value = serde_json::from_str(&strip_json_comments(&content)).ok()?;
+    let dir = path.parent()?;
+
+    let mut options = CompilerOptions::default();
+
+    // the later extended tsconfig overrides the former one, the tsconfig itself overrides all of them
+    let extends = match raw.get("extends") {
+        Some(Value::String(extends)) => vec![extends.as_str()],
+        Some(Value::Array(extends)) => extends.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+
+    for extends in extends {
+        let Some(extended) = resolve_extends(dir, extends)
+            .and_then(|extended_path| parse_tsconfig(&extended_path, files))
+        else {
+            continue;
+        };
+
+        if extended.base_url.is_some() {
+            options.base_url = extended.base_url;
+        }
+        if extended.paths.is_some() {
+            options.paths = extended.paths;
+        }
+    }
+
+    let compiler_options = raw.get("compilerOptions");
+
+    if let Some(base_url) = compiler_options
+        .and_then(|options| options.get("baseUrl"))
+        .and_then(Value::as_str)
+    {
+        options.base_url = Some(normalize_path(&dir.join(base_url)));
+    }
+
+    if let Some(paths) = compiler_options
+        .and_then(|options| options.get("paths"))
+        .and_then(Value::as_object)
+    {
+        let paths = paths
+            .iter()
+            .map(|(pattern, targets)| {
+                let targets = targets
+                    .as_array()
+                    .map(|targets| {
+                        targets
+                            .iter()
+                            .filter_map(|target| target.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                (pattern.clone(), targets)
+            })
+            .collect();
+
+        options.paths = Some((paths, dir.to_path_buf()));
+    }
+
+    Some(options)
+}
+
+/// `extends` is a path relative to the tsconfig, or a path in a package like `@tsconfig/node18/tsconfig.json`.
+/// `.json` is appended if the file does not exist, a package itself is resolved to its tsconfig.json.
+fn resolve_extends(dir: &Path, extends: &str) -> Option<PathBuf> {
+    let try_file = |path: PathBuf| {
+        if path.is_file() {
+            return Some(path);
+        }
+
+        if path.is_dir() {
+            return Some(path.join(TSCONFIG_FILE_NAME)).filter(|path| path.is_file());
+        }
+
+        let mut with_json = path.into_os_string();
+        with_json.push(".json");
+        Some(PathBuf::from(with_json)).filter(|path| path.is_file())
+    };
+
+    if extends.starts_with('.') || Path::new(extends).is_absolute() {
+        return try_file(normalize_path(&dir.join(extends)));
+    }
+
+    dir.ancestors()
+        .find_map(|dir| try_file(dir.join("node_modules").join(extends)))
+}
+
+/// tsconfig allows comments and trailing commas, remove them so that it can be parsed as json
+fn strip_json_comments(content: &str) -> String {
+    let chars = content.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    // index of the next char that is not whitespace or a comment
+    let next_significant = |mut i: usize| {
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+            } else if let Some(end) = skip_comment(&chars, i) {
+                i = end;
+            } else {
+                return Some(chars[i]);
+            }
+        }
+
+        None
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if in_string {
+            result.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.get(i + 1) {
+                    result.push(*escaped);
+                    i += 1;
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+        } else if let Some(end) = skip_comment(&chars, i) {
+            i = end;
+        } else if c == ',' && matches!(next_significant(i + 1), Some('}') | Some(']')) {
+            i += 1;
+        } else {
+            in_string = c == '"';
+            result.push(c);
+            i += 1;
+        }
+    }
+
+    result
+}
+
+/// the index after the comment starting at `i`, [None] if there is no comment at `i`
+fn skip_comment(chars: &[char], i: usize) -> Option<usize> {
+    match (chars.get(i), chars.get(i + 1)) {
+        (Some('/'), Some('/')) => Some(
+            (i..chars.len())
+                .find(|&i| chars[i] == '\n')
+                .unwrap_or(chars.len()),
+        ),
+        (Some('/'), Some('*')) => Some(
+            (i + 2..chars.len())
+                .find(|&i| chars[i] == '*' && chars.get(i + 1) == Some(&'/'))
+                .map_or(chars.len(), |i| i + 2),
+        ),
+        _ => None,
+    }
+}
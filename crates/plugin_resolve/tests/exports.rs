@@ -0,0 +1,168 @@
+use std::{path::PathBuf, sync::Arc};
+
+use toy_farm_core::{
+    error::Result, CompilationContext, CompilationError, Config, ModuleId, Plugin,
+    PluginResolveHookParam, ResolveConfig, ResolveKind,
+};
+use toy_farm_plugin_resolve::FarmPluginResolve;
+
+fn fixture_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/exports")
+}
+
+async fn resolve_with(
+    source: &str,
+    kind: ResolveKind,
+    resolve_config: ResolveConfig,
+) -> Result<Option<String>> {
+    let root = fixture_root();
+    let config = Config {
+        root: root.to_string_lossy().to_string(),
+        resolve: resolve_config,
+        ..Default::default()
+    };
+    let plugin = FarmPluginResolve::new(&config).unwrap();
+    let context = Arc::new(CompilationContext::new(config, vec![]).unwrap());
+    let importer = root.join("src/index.js");
+
+    let result = plugin
+        .resolve(
+            Arc::new(PluginResolveHookParam {
+                source: source.to_string(),
+                importer: Some(ModuleId::new(&importer.to_string_lossy(), "")),
+                kind,
+            }),
+            context,
+            Default::default(),
+        )
+        .await?;
+
+    Ok(result.map(|result| {
+        PathBuf::from(result.resolved_path)
+            .strip_prefix(&root)
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    }))
+}
+
+async fn resolve(source: &str) -> Result<Option<String>> {
+    resolve_with(source, ResolveKind::Import, ResolveConfig::default()).await
+}
+
+fn strict() -> ResolveConfig {
+    ResolveConfig {
+        strict_exports: true,
+        ..Default::default()
+    }
+}
+
+fn error_message(result: Result<Option<String>>) -> String {
+    match result {
+        Err(CompilationError::ResolveError {
+            source: Some(source),
+            ..
+        }) => source.to_string(),
+        result => panic!("expected a resolve error, got {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn exports_conditions() {
+    assert_eq!(
+        resolve("pkg").await.unwrap().unwrap(),
+        "node_modules/pkg/dist/index.mjs"
+    );
+    assert_eq!(
+        resolve_with("pkg", ResolveKind::Require, ResolveConfig::default())
+            .await
+            .unwrap()
+            .unwrap(),
+        "node_modules/pkg/dist/index.cjs"
+    );
+
+    // nested conditions are matched in the order of the keys in package.json
+    assert_eq!(
+        resolve("pkg/nested").await.unwrap().unwrap(),
+        "node_modules/pkg/dist/nested.module.js"
+    );
+    let custom = ResolveConfig {
+        conditions: vec!["module".to_string(), "custom".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        resolve_with("pkg/nested", ResolveKind::Import, custom)
+            .await
+            .unwrap()
+            .unwrap(),
+        "node_modules/pkg/dist/nested.custom.js"
+    );
+}
+
+#[tokio::test]
+async fn exports_patterns() {
+    assert_eq!(
+        resolve("pkg/features/a.js").await.unwrap().unwrap(),
+        "node_modules/pkg/dist/features/a.js"
+    );
+
+    // the longer pattern excludes the subpath with `null`
+    let message = error_message(
+        resolve_with(
+            "pkg/features/private/secret.js",
+            ResolveKind::Import,
+            strict(),
+        )
+        .await,
+    );
+    assert!(
+        message.contains("`./features/private/secret.js` is not defined by \"exports\""),
+        "{}",
+        message
+    );
+}
+
+#[tokio::test]
+async fn strict_exports() {
+    // deep imports fall back to the files of the package by default
+    assert_eq!(
+        resolve("pkg/dist/deep.js").await.unwrap().unwrap(),
+        "node_modules/pkg/dist/deep.js"
+    );
+
+    let message =
+        error_message(resolve_with("pkg/dist/deep.js", ResolveKind::Import, strict()).await);
+    assert!(
+        message.contains("Package subpath `./dist/deep.js` is not defined by \"exports\"")
+            && message.contains("node_modules/pkg/package.json"),
+        "{}",
+        message
+    );
+
+    let message = error_message(resolve_with("pkg/invalid", ResolveKind::Import, strict()).await);
+    assert!(
+        message.contains("Invalid target `../outside.js`"),
+        "{}",
+        message
+    );
+}
+
+#[tokio::test]
+async fn imports() {
+    assert_eq!(
+        resolve("#utils/a").await.unwrap().unwrap(),
+        "src/utils/a.js"
+    );
+    assert_eq!(resolve("#env").await.unwrap().unwrap(), "src/env.dev.js");
+    assert_eq!(
+        resolve("#dep").await.unwrap().unwrap(),
+        "node_modules/pkg/dist/index.mjs"
+    );
+
+    let message = error_message(resolve("#missing").await);
+    assert!(
+        message.contains("`#missing` is not defined by \"imports\""),
+        "{}",
+        message
+    );
+}
@@ -0,0 +1 @@
+import { button } from '@app/button';
@@ -0,0 +1 @@
+import { add } from '@shared/math';
@@ -0,0 +1,245 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use toy_farm_core::{
+    error::Result, CompilationContext, CompilationError, Config, ModuleId, Plugin,
+    PluginResolveHookParam, ResolveConfig, ResolveKind,
+};
+use toy_farm_plugin_resolve::FarmPluginResolve;
+
+fn fixture_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/resolve")
+}
+
+/// resolve `source` imported by `src/index.js`, return the resolved path relative to the fixture root
+async fn resolve_with(source: &str, resolve_config: ResolveConfig) -> Option<PathBuf> {
+    let config = Config {
+        root: fixture_root().to_string_lossy().to_string(),
+        resolve: resolve_config,
+        ..Default::default()
+    };
+    let plugin = FarmPluginResolve::new(&config).unwrap();
+    let context = Arc::new(CompilationContext::new(config, vec![]).unwrap());
+
+    resolve_by(&plugin, context, source).await
+}
+
+async fn resolve_by(
+    plugin: &FarmPluginResolve,
+    context: Arc<CompilationContext>,
+    source: &str,
+) -> Option<PathBuf> {
+    let root = fixture_root();
+    let importer = root.join("src/index.js");
+
+    let result = plugin
+        .resolve(
+            Arc::new(PluginResolveHookParam {
+                source: source.to_string(),
+                importer: Some(ModuleId::new(&importer.to_string_lossy(), "")),
+                kind: ResolveKind::Import,
+            }),
+            context,
+            Default::default(),
+        )
+        .await
+        .unwrap();
+
+    result.map(|result| {
+        let resolved_path = PathBuf::from(result.resolved_path);
+        assert!(resolved_path.is_file(), "{:?}", resolved_path);
+        resolved_path.strip_prefix(&root).unwrap().to_path_buf()
+    })
+}
+
+async fn resolve(source: &str) -> Option<PathBuf> {
+    resolve_with(source, ResolveConfig::default()).await
+}
+
+#[tokio::test]
+async fn extensions() {
+    // extensions are tried in order, `ts` comes before `js` by default
+    assert_eq!(resolve("./dep").await.unwrap(), PathBuf::from("src/dep.ts"));
+    assert_eq!(
+        resolve("./dep.js").await.unwrap(),
+        PathBuf::from("src/dep.js")
+    );
+
+    let js_first = ResolveConfig {
+        extensions: vec!["js".to_string(), "ts".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        resolve_with("./dep", js_first).await.unwrap(),
+        PathBuf::from("src/dep.js")
+    );
+
+    let no_extensions = ResolveConfig {
+        extensions: vec![],
+        ..Default::default()
+    };
+    assert_eq!(resolve_with("./dep", no_extensions).await, None);
+}
+
+#[tokio::test]
+async fn main_files() {
+    assert_eq!(
+        resolve("./dir").await.unwrap(),
+        PathBuf::from("src/dir/index.ts")
+    );
+    assert_eq!(
+        resolve("no-main").await.unwrap(),
+        PathBuf::from("node_modules/no-main/index.js")
+    );
+
+    let no_main_files = ResolveConfig {
+        main_files: vec![],
+        ..Default::default()
+    };
+    assert_eq!(resolve_with("./dir", no_main_files).await, None);
+}
+
+#[tokio::test]
+async fn main_fields() {
+    // `module` comes before `main` by default, and the entry is resolved with extensions
+    assert_eq!(
+        resolve("module-pkg").await.unwrap(),
+        PathBuf::from("node_modules/module-pkg/es/index.js")
+    );
+
+    let main_only = ResolveConfig {
+        main_fields: vec!["main".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        resolve_with("module-pkg", main_only).await.unwrap(),
+        PathBuf::from("node_modules/module-pkg/lib/index.js")
+    );
+
+    // `browser` with an object value is not an entry
+    assert_eq!(
+        resolve("browser-map").await.unwrap(),
+        PathBuf::from("node_modules/browser-map/lib/node.js")
+    );
+
+    // the entry can be a directory containing main files
+    assert_eq!(
+        resolve("main-dir").await.unwrap(),
+        PathBuf::from("node_modules/main-dir/lib/index.js")
+    );
+}
+
+#[tokio::test]
+async fn alias() {
+    let root = fixture_root();
+    let alias = ResolveConfig {
+        alias: HashMap::from([
+            ("@/".to_string(), "src/".to_string()),
+            ("utils$".to_string(), "src/dep".to_string()),
+            (
+                "$__farm_regex:^comp-(.*)$".to_string(),
+                "src/components/$1".to_string(),
+            ),
+            ("pkg-alias".to_string(), "module-pkg".to_string()),
+            (
+                "abs".to_string(),
+                root.join("src/dir").to_string_lossy().to_string(),
+            ),
+        ]),
+        ..Default::default()
+    };
+    let resolve = |source: &'static str| resolve_with(source, alias.clone());
+
+    // prefix aliases are resolved from the root with extensions
+    assert_eq!(
+        resolve("@/components/button").await.unwrap(),
+        PathBuf::from("src/components/button.tsx")
+    );
+    // scoped packages are not matched by `@/`
+    assert_eq!(
+        resolve("@scope/lib").await.unwrap(),
+        PathBuf::from("node_modules/@scope/lib/index.js")
+    );
+
+    // `$` matches the source exactly
+    assert_eq!(resolve("utils").await.unwrap(), PathBuf::from("src/dep.ts"));
+    assert_eq!(resolve("utils/dep").await, None);
+
+    assert_eq!(
+        resolve("comp-button").await.unwrap(),
+        PathBuf::from("src/components/button.tsx")
+    );
+
+    // aliased packages are resolved with their main fields
+    assert_eq!(
+        resolve("pkg-alias").await.unwrap(),
+        PathBuf::from("node_modules/module-pkg/es/index.js")
+    );
+    assert_eq!(
+        resolve("pkg-alias/lib").await.unwrap(),
+        PathBuf::from("node_modules/module-pkg/lib/index.js")
+    );
+
+    assert_eq!(
+        resolve("abs").await.unwrap(),
+        PathBuf::from("src/dir/index.ts")
+    );
+}
+
+#[tokio::test]
+async fn config_resolved() {
+    let config = Config {
+        root: fixture_root().to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let plugin = FarmPluginResolve::new(&config).unwrap();
+
+    // the config patched by the `config` hooks is resolved after the plugin is created
+    let config = Config {
+        resolve: ResolveConfig {
+            extensions: vec!["js".to_string(), "ts".to_string()],
+            ..Default::default()
+        },
+        ..config
+    };
+    plugin.config_resolved(&config).await.unwrap();
+    let context = Arc::new(CompilationContext::new(config, vec![]).unwrap());
+
+    assert_eq!(
+        resolve_by(&plugin, context, "./dep").await.unwrap(),
+        PathBuf::from("src/dep.js")
+    );
+}
+
+fn assert_invalid_alias<T>(result: Result<T>) {
+    match result {
+        Err(CompilationError::GenericError(msg)) => {
+            assert!(msg.contains("`$__farm_regex:comp-(`"), "{}", msg)
+        }
+        Err(e) => panic!("expected an invalid alias error, got {:?}", e),
+        Ok(_) => panic!("expected an invalid alias error"),
+    }
+}
+
+#[tokio::test]
+async fn invalid_regex_alias() {
+    let valid_config = Config {
+        root: fixture_root().to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let config = Config {
+        resolve: ResolveConfig {
+            alias: HashMap::from([(
+                "$__farm_regex:comp-(".to_string(),
+                "src/components/$1".to_string(),
+            )]),
+            ..Default::default()
+        },
+        ..valid_config.clone()
+    };
+
+    assert_invalid_alias(FarmPluginResolve::new(&config));
+
+    // the alias patched by the `config` hooks is validated when the options are rebuilt
+    let plugin = FarmPluginResolve::new(&valid_config).unwrap();
+    assert_invalid_alias(plugin.config_resolved(&config).await);
+}
@@ -0,0 +1,129 @@
+#![cfg(unix)]
+
+use std::{
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use toy_farm_core::{
+    CompilationContext, Config, ModuleId, Plugin, PluginResolveHookParam, ResolveConfig,
+    ResolveKind,
+};
+use toy_farm_plugin_resolve::FarmPluginResolve;
+
+/// ```md
+/// node_modules/.pnpm/lib@1.0.0/node_modules/lib/index.js
+/// node_modules/lib -> .pnpm/lib@1.0.0/node_modules/lib
+/// packages/app/node_modules/lib -> ../../../node_modules/.pnpm/lib@1.0.0/node_modules/lib
+/// packages/app/index.js
+/// src/index.js
+/// src/dep.js
+/// ```
+fn create_pnpm_layout(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&root);
+
+    let store = root.join("node_modules/.pnpm/lib@1.0.0/node_modules/lib");
+    std::fs::create_dir_all(&store).unwrap();
+    std::fs::write(store.join("index.js"), "export default 'lib';").unwrap();
+    symlink(
+        ".pnpm/lib@1.0.0/node_modules/lib",
+        root.join("node_modules/lib"),
+    )
+    .unwrap();
+
+    std::fs::create_dir_all(root.join("packages/app/node_modules")).unwrap();
+    std::fs::write(root.join("packages/app/index.js"), "import 'lib';").unwrap();
+    symlink(
+        "../../../node_modules/.pnpm/lib@1.0.0/node_modules/lib",
+        root.join("packages/app/node_modules/lib"),
+    )
+    .unwrap();
+
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/index.js"), "import 'lib';").unwrap();
+    std::fs::write(root.join("src/dep.js"), "export const dep = 'dep';").unwrap();
+
+    root
+}
+
+/// resolve `source` imported by `importer`, return the resolved path relative to the root
+async fn resolve(root: &Path, symlinks: bool, source: &str, importer: &str) -> PathBuf {
+    let config = Config {
+        root: root.to_string_lossy().to_string(),
+        resolve: ResolveConfig {
+            symlinks,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let plugin = FarmPluginResolve::new(&config).unwrap();
+    let context = Arc::new(CompilationContext::new(config, vec![]).unwrap());
+    let importer = root.join(importer);
+
+    let result = plugin
+        .resolve(
+            Arc::new(PluginResolveHookParam {
+                source: source.to_string(),
+                importer: Some(ModuleId::new(&importer.to_string_lossy(), "")),
+                kind: ResolveKind::Import,
+            }),
+            context,
+            Default::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    PathBuf::from(result.resolved_path)
+        .strip_prefix(root)
+        .unwrap()
+        .to_path_buf()
+}
+
+#[tokio::test]
+async fn symlinks_resolved_to_real_path() {
+    let root = create_pnpm_layout("toy_farm_resolve_symlinks");
+    let real_path = PathBuf::from("node_modules/.pnpm/lib@1.0.0/node_modules/lib/index.js");
+
+    // the same package reached through different links is resolved to the same file
+    assert_eq!(resolve(&root, true, "lib", "src/index.js").await, real_path);
+    assert_eq!(
+        resolve(&root, true, "lib", "packages/app/index.js").await,
+        real_path
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn symlinks_kept() {
+    let root = create_pnpm_layout("toy_farm_resolve_symlinks_kept");
+
+    assert_eq!(
+        resolve(&root, false, "lib", "src/index.js").await,
+        PathBuf::from("node_modules/lib/index.js")
+    );
+    assert_eq!(
+        resolve(&root, false, "lib", "packages/app/index.js").await,
+        PathBuf::from("packages/app/node_modules/lib/index.js")
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn stable_resolved_path() {
+    let root = create_pnpm_layout("toy_farm_resolve_stable_path");
+
+    // `.` and `..` segments are removed with or without resolving the symlinks
+    for symlinks in [true, false] {
+        for source in ["./dep", "./dep.js", "../src/./dep.js"] {
+            let resolved_path = resolve(&root, symlinks, source, "src/index.js").await;
+            assert_eq!(resolved_path.to_string_lossy(), "src/dep.js");
+        }
+    }
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
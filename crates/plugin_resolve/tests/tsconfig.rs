@@ -0,0 +1,206 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use toy_farm_core::{
+    CompilationContext, Config, ModuleId, Plugin, PluginResolveHookParam,
+    PluginUpdateModulesHookParam, ResolveKind, UpdateType,
+};
+use toy_farm_plugin_resolve::FarmPluginResolve;
+
+fn fixture_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tsconfig")
+}
+
+fn create(root: &Path) -> (FarmPluginResolve, Arc<CompilationContext>) {
+    let config = Config {
+        root: root.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let plugin = FarmPluginResolve::new(&config).unwrap();
+    let context = Arc::new(CompilationContext::new(config, vec![]).unwrap());
+
+    (plugin, context)
+}
+
+/// resolve `source` imported by `importer`, return the resolved path relative to the root
+async fn resolve(
+    plugin: &FarmPluginResolve,
+    context: &Arc<CompilationContext>,
+    source: &str,
+    importer: &str,
+) -> Option<PathBuf> {
+    let root = PathBuf::from(&context.config.root);
+    let importer = root.join(importer);
+
+    let result = plugin
+        .resolve(
+            Arc::new(PluginResolveHookParam {
+                source: source.to_string(),
+                importer: Some(ModuleId::new(&importer.to_string_lossy(), "")),
+                kind: ResolveKind::Import,
+            }),
+            context.clone(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+
+    result.map(|result| {
+        PathBuf::from(result.resolved_path)
+            .strip_prefix(&root)
+            .unwrap()
+            .to_path_buf()
+    })
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let to = to.join(entry.file_name());
+
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &to);
+        } else {
+            std::fs::copy(entry.path(), to).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn paths() {
+    let (plugin, context) = create(&fixture_root());
+    let app = "packages/app/src/index.ts";
+
+    assert_eq!(
+        resolve(&plugin, &context, "@app/button", app)
+            .await
+            .unwrap(),
+        PathBuf::from("packages/app/src/button.tsx")
+    );
+    // targets are tried in order
+    assert_eq!(
+        resolve(&plugin, &context, "@app/legacy", app)
+            .await
+            .unwrap(),
+        PathBuf::from("packages/app/fallback/legacy.js")
+    );
+    // exact patterns win over node_modules
+    assert_eq!(
+        resolve(&plugin, &context, "config", app).await.unwrap(),
+        PathBuf::from("packages/app/src/config.ts")
+    );
+    // `baseUrl` is inherited from the extended tsconfig
+    assert_eq!(
+        resolve(&plugin, &context, "shared/math", app)
+            .await
+            .unwrap(),
+        PathBuf::from("shared/math.ts")
+    );
+    // `paths` of the extended tsconfig is overridden
+    assert_eq!(resolve(&plugin, &context, "@shared/math", app).await, None);
+
+    assert_eq!(
+        resolve(
+            &plugin,
+            &context,
+            "@shared/math",
+            "packages/lib/src/index.ts"
+        )
+        .await
+        .unwrap(),
+        PathBuf::from("shared/math.ts")
+    );
+}
+
+#[tokio::test]
+async fn invalidate_tsconfig() {
+    let root = std::env::temp_dir().join("toy_farm_resolve_tsconfig");
+    let _ = std::fs::remove_dir_all(&root);
+    copy_dir(&fixture_root(), &root);
+
+    let (plugin, context) = create(&root);
+    let app = "packages/app/src/index.ts";
+    let app_tsconfig = root.join("packages/app/tsconfig.json");
+
+    assert_eq!(
+        resolve(&plugin, &context, "@app/button", app)
+            .await
+            .unwrap(),
+        PathBuf::from("packages/app/src/button.tsx")
+    );
+
+    // the importer watches the tsconfig and the tsconfig it extends
+    {
+        let watch_graph = context.watch_graph.read().await;
+        let importer = ModuleId::new(&root.join(app).to_string_lossy(), "");
+
+        for tsconfig in [&app_tsconfig, &root.join("tsconfig.base.json")] {
+            let tsconfig = ModuleId::new(&tsconfig.to_string_lossy(), "");
+            assert_eq!(watch_graph.relation_roots(&tsconfig), vec![&importer]);
+        }
+    }
+
+    std::fs::write(
+        &app_tsconfig,
+        r#"{ "extends": "../../tsconfig.base.json", "compilerOptions": { "paths": { "@app/*": ["packages/app/fallback/*"] } } }"#,
+    )
+    .unwrap();
+
+    // cached until the tsconfig is updated
+    assert!(resolve(&plugin, &context, "@app/button", app)
+        .await
+        .is_some());
+
+    plugin
+        .update_modules(
+            &mut PluginUpdateModulesHookParam {
+                paths: vec![(
+                    "packages/app/tsconfig.json".to_string(),
+                    UpdateType::Updated,
+                )],
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(resolve(&plugin, &context, "@app/button", app).await, None);
+    assert_eq!(
+        resolve(&plugin, &context, "@app/legacy", app)
+            .await
+            .unwrap(),
+        PathBuf::from("packages/app/fallback/legacy.js")
+    );
+
+    // updating the extended tsconfig invalidates the tsconfigs extending it
+    std::fs::write(
+        root.join("tsconfig.base.json"),
+        r#"{ "compilerOptions": { "baseUrl": "shared" } }"#,
+    )
+    .unwrap();
+    plugin
+        .update_modules(
+            &mut PluginUpdateModulesHookParam {
+                paths: vec![(
+                    root.join("tsconfig.base.json")
+                        .to_string_lossy()
+                        .to_string(),
+                    UpdateType::Updated,
+                )],
+            },
+            &context,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        resolve(&plugin, &context, "math", app).await.unwrap(),
+        PathBuf::from("shared/math.ts")
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
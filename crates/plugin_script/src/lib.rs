@@ -0,0 +1,391 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use toy_farm_core::{
+    error::Result, CompilationContext, ModuleGraph, ModuleId, ModuleMetaData, ModuleType, Plugin,
+    PluginGenerateResourcesHookParam, PluginHookFilter, PluginHookFilters, PluginParseHookParam,
+    Resource, ResourceType, ScriptModuleMetaData,
+};
+use toy_farm_toolkit::script::{
+    codegen_module, module_system_from_ast, parse_module,
+    swc_common::{comments::SingleThreadedComments, Mark, SyntaxContext, GLOBALS},
+    swc_ecma_ast::{CallExpr, Callee, EsVersion, Expr, Lit, ModuleDecl, ModuleItem, Program, Str},
+    swc_ecma_transforms_base::{
+        fixer::fixer,
+        helpers::{inject_helpers, Helpers, HELPERS},
+        hygiene::hygiene,
+        resolver,
+    },
+    swc_ecma_transforms_module::common_js::common_js,
+    swc_ecma_transforms_typescript::strip,
+    swc_ecma_visit::{VisitMut, VisitMutWith},
+    syntax_from_module_type, ParseScriptModuleResult,
+};
+
+/// Wraps the modules of an entry, every module is a function of `(module, exports, require)` keyed by its path relative to the root.
+const RUNTIME_PREFIX: &str = "(function(modules){var cache={};function require(id){if(cache[id])return cache[id].exports;if(!modules[id])throw new Error(\"Cannot find module '\"+id+\"'\");var module=cache[id]={exports:{}};modules[id](module,module.exports,require);return module.exports;}";
+
+/// Built-in script plugin, parse js/jsx/ts/tsx modules into [ScriptModuleMetaData] and generate the script resource of each entry.
+/// The script modules reachable from the entry, dynamic imports included, are transformed to commonjs and bundled into one resource with a tiny runtime.
+pub struct FarmPluginScript {}
+
+impl FarmPluginScript {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for FarmPluginScript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for FarmPluginScript {
+    fn name(&self) -> &str {
+        "FarmPluginScript"
+    }
+
+    fn priority(&self) -> i32 {
+        99
+    }
+
+    fn hook_filters(&self) -> PluginHookFilters {
+        PluginHookFilters {
+            parse: PluginHookFilter {
+                module_types: HashSet::from([
+                    ModuleType::Js,
+                    ModuleType::Jsx,
+                    ModuleType::Ts,
+                    ModuleType::Tsx,
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn parse(
+        &self,
+        param: Arc<PluginParseHookParam>,
+        context: Arc<CompilationContext>,
+    ) -> Result<Option<ModuleMetaData>> {
+        let Some(syntax) = syntax_from_module_type(&param.module_type) else {
+            return Ok(None);
+        };
+
+        let ParseScriptModuleResult { mut ast, comments } = parse_module(
+            &param.module_id.to_string(),
+            &param.content,
+            syntax,
+            EsVersion::latest(),
+        )?;
+
+        let meta = GLOBALS.set(&context.meta.script.globals, || {
+            let top_level_mark = Mark::new();
+            let unresolved_mark = Mark::new();
+            let is_typescript = matches!(param.module_type, ModuleType::Ts | ModuleType::Tsx);
+
+            ast.visit_mut_with(&mut resolver(
+                unresolved_mark,
+                top_level_mark,
+                is_typescript,
+            ));
+
+            let module_system = module_system_from_ast(&ast, unresolved_mark);
+
+            ScriptModuleMetaData {
+                ast,
+                top_level_mark: top_level_mark.as_u32(),
+                unresolved_mark: unresolved_mark.as_u32(),
+                module_system,
+                hmr_self_accepted: false,
+                hmr_accepted_deps: Default::default(),
+                comments: comments.into(),
+            }
+        });
+
+        Ok(Some(ModuleMetaData::Script(meta)))
+    }
+
+    async fn generate_resources(
+        &self,
+        param: &PluginGenerateResourcesHookParam,
+        context: &Arc<CompilationContext>,
+    ) -> Result<Option<Vec<Resource>>> {
+        let root = &context.config.root;
+        let mut modules = vec![];
+
+        for module_id in &registered_modules(param.module_graph, param.modules) {
+            let Some(module) = param.module_graph.module(module_id) else {
+                continue;
+            };
+
+            if module.external || !is_script(&module.module_type) {
+                continue;
+            }
+
+            let code = GLOBALS.set(&context.meta.script.globals, || {
+                transform_to_commonjs(
+                    module.meta.as_script(),
+                    &module.module_type,
+                    &dependency_keys(param.module_graph, module_id, root),
+                    context.config.minify,
+                )
+            });
+
+            modules.push((registry_key(module_id, root), code));
+        }
+
+        if modules.is_empty() {
+            return Ok(None);
+        }
+
+        // a script entry is executed itself, the scripts of an html entry are executed in the order of the document
+        let entry_module_type = param
+            .module_graph
+            .module(param.entry)
+            .map(|entry| entry.module_type.clone());
+        let executed = if entry_module_type.as_ref().is_some_and(is_script) {
+            vec![registry_key(param.entry, root)]
+        } else {
+            param
+                .module_graph
+                .dependencies(param.entry)
+                .into_iter()
+                .filter(|(dep, _)| {
+                    param
+                        .module_graph
+                        .module(dep)
+                        .is_some_and(|dep| !dep.external && is_script(&dep.module_type))
+                })
+                .map(|(dep, _)| registry_key(&dep, root))
+                .collect()
+        };
+
+        let mut code = RUNTIME_PREFIX.to_string();
+
+        for key in executed {
+            code.push_str(&format!("require({});", js_string(&key)));
+        }
+
+        code.push_str("})({\n");
+
+        for (key, module_code) in modules {
+            code.push_str(&format!(
+                "{}:function(module,exports,require){{\n{}}},\n",
+                js_string(&key),
+                module_code
+            ));
+        }
+
+        code.push_str("});\n");
+
+        Ok(Some(vec![Resource {
+            name: context
+                .config
+                .output
+                .resource_filename(param.entry_name, &ResourceType::Js.to_ext()),
+            bytes: code.into_bytes(),
+            resource_type: ResourceType::Js,
+            entry: Some(param.entry_name.to_string()),
+        }]))
+    }
+}
+
+fn is_script(module_type: &ModuleType) -> bool {
+    matches!(
+        module_type,
+        ModuleType::Js | ModuleType::Jsx | ModuleType::Ts | ModuleType::Tsx
+    )
+}
+
+/// The modules of the entry and the modules reachable from them through dynamic imports, which are required lazily
+/// by the runtime so they are registered in the same resource.
+fn registered_modules(module_graph: &ModuleGraph, modules: &[ModuleId]) -> Vec<ModuleId> {
+    let mut registered = modules.to_vec();
+    let mut visited = modules.iter().cloned().collect::<HashSet<_>>();
+    let mut stack = modules.to_vec();
+
+    while let Some(module_id) = stack.pop() {
+        for (dep, _) in module_graph.dependencies(&module_id) {
+            if visited.insert(dep.clone()) {
+                registered.push(dep.clone());
+                stack.push(dep);
+            }
+        }
+    }
+
+    registered
+}
+
+/// the key of the module in the runtime, the path relative to the root, e.g. `src/index.ts`
+fn registry_key(module_id: &ModuleId, root: &str) -> String {
+    let id = module_id.to_string();
+
+    Path::new(&id)
+        .strip_prefix(root)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or(id)
+}
+
+/// Map the sources of the module to the keys of the dependencies, [None] for the dependencies bundled into other resources, like css.
+/// The sources of external dependencies are kept as is.
+fn dependency_keys(
+    module_graph: &ModuleGraph,
+    module_id: &ModuleId,
+    root: &str,
+) -> HashMap<String, Option<String>> {
+    let mut keys = HashMap::new();
+
+    for (dep, edge) in module_graph.dependencies(module_id) {
+        let Some(dep_module) = module_graph.module(&dep) else {
+            continue;
+        };
+
+        if dep_module.external {
+            continue;
+        }
+
+        let key = is_script(&dep_module.module_type).then(|| registry_key(&dep, root));
+
+        for item in edge.items() {
+            keys.insert(item.source.clone(), key.clone());
+        }
+    }
+
+    keys
+}
+
+/// Transform the ast of the module to commonjs code, must be called under the [GLOBALS] of the module marks.
+fn transform_to_commonjs(
+    meta: &ScriptModuleMetaData,
+    module_type: &ModuleType,
+    dependency_keys: &HashMap<String, Option<String>>,
+    minify: bool,
+) -> String {
+    let top_level_mark = Mark::from_u32(meta.top_level_mark);
+    let unresolved_mark = Mark::from_u32(meta.unresolved_mark);
+    let mut ast = meta.ast.clone();
+
+    ast.visit_mut_with(&mut SourceReplacer {
+        dependency_keys,
+        unresolved_ctxt: SyntaxContext::empty().apply_mark(unresolved_mark),
+    });
+
+    if matches!(module_type, ModuleType::Ts | ModuleType::Tsx) {
+        // the typescript transform only accepts a program
+        let mut program = Program::Module(ast);
+        program.visit_mut_with(&mut strip(top_level_mark));
+        ast = program.expect_module();
+    }
+
+    HELPERS.set(&Helpers::new(false), || {
+        ast.visit_mut_with(&mut common_js(
+            unresolved_mark,
+            Default::default(),
+            Default::default(),
+            None::<SingleThreadedComments>,
+        ));
+        ast.visit_mut_with(&mut inject_helpers(unresolved_mark));
+    });
+
+    ast.visit_mut_with(&mut hygiene());
+    ast.visit_mut_with(&mut fixer(None));
+
+    codegen_module(&ast, minify)
+}
+
+/// Replace the sources of `import`, `export from`, `import()` and `require()` with the keys of the dependencies,
+/// side effect imports of the dependencies bundled into other resources are removed.
+struct SourceReplacer<'a> {
+    dependency_keys: &'a HashMap<String, Option<String>>,
+    unresolved_ctxt: SyntaxContext,
+}
+
+impl SourceReplacer<'_> {
+    fn replace(&self, src: &mut Str) {
+        if let Some(Some(key)) = self.dependency_keys.get(&*src.value) {
+            src.value = key.as_str().into();
+            src.raw = None;
+        }
+    }
+}
+
+impl VisitMut for SourceReplacer<'_> {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.retain(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) if import.specifiers.is_empty() => {
+                !matches!(self.dependency_keys.get(&*import.src.value), Some(None))
+            }
+            _ => true,
+        });
+
+        for item in items.iter_mut() {
+            match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => self.replace(&mut import.src),
+                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => {
+                    if let Some(src) = &mut export.src {
+                        self.replace(src);
+                    }
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export)) => {
+                    self.replace(&mut export.src)
+                }
+                _ => {}
+            }
+        }
+
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_call_expr(&mut self, call_expr: &mut CallExpr) {
+        call_expr.visit_mut_children_with(self);
+
+        let is_import_or_require = match &call_expr.callee {
+            Callee::Import(_) => true,
+            Callee::Expr(callee) => matches!(
+                &**callee,
+                Expr::Ident(ident) if &*ident.sym == "require" && ident.span.ctxt == self.unresolved_ctxt
+            ),
+            _ => false,
+        };
+
+        if !is_import_or_require {
+            return;
+        }
+
+        if let Some(arg) = call_expr.args.first_mut() {
+            if let Expr::Lit(Lit::Str(src)) = &mut *arg.expr {
+                self.replace(src);
+            }
+        }
+    }
+}
+
+/// quote the string as a js string literal, line terminators and other control characters are escaped
+fn js_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                quoted.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use toy_farm_core::{
+    CompilationContext, CompilationError, Config, ModuleMetaData, ModuleSystem, ModuleType, Plugin,
+    PluginParseHookParam,
+};
+use toy_farm_plugin_script::FarmPluginScript;
+
+async fn parse(
+    content: &str,
+    module_type: ModuleType,
+) -> toy_farm_core::error::Result<Option<ModuleMetaData>> {
+    let context = Arc::new(CompilationContext::new(Config::default(), vec![]).unwrap());
+    let param = PluginParseHookParam {
+        module_id: "index".into(),
+        resolved_path: "/root/index".to_string(),
+        query: vec![],
+        module_type,
+        content: Arc::new(content.to_string()),
+    };
+
+    FarmPluginScript::new()
+        .parse(Arc::new(param), context)
+        .await
+}
+
+async fn parse_module_system(content: &str, module_type: ModuleType) -> ModuleSystem {
+    let meta = parse(content, module_type).await.unwrap().unwrap();
+    meta.as_script().module_system.clone()
+}
+
+#[tokio::test]
+async fn parse_script_module_types() {
+    assert_eq!(
+        parse_module_system("export const a = 1;", ModuleType::Js).await,
+        ModuleSystem::EsModule
+    );
+    assert_eq!(
+        parse_module_system("export const a = <div />;", ModuleType::Jsx).await,
+        ModuleSystem::EsModule
+    );
+    assert_eq!(
+        parse_module_system("export const a: number = 1;", ModuleType::Ts).await,
+        ModuleSystem::EsModule
+    );
+    assert_eq!(
+        parse_module_system(
+            "export const a = <div>{1 as number}</div>;",
+            ModuleType::Tsx
+        )
+        .await,
+        ModuleSystem::EsModule
+    );
+
+    // non script modules are left to other plugins
+    assert!(parse(".a {}", ModuleType::Css).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn detect_module_system() {
+    assert_eq!(
+        parse_module_system(
+            "const a = require('./a'); module.exports = a;",
+            ModuleType::Js
+        )
+        .await,
+        ModuleSystem::CommonJs
+    );
+    assert_eq!(
+        parse_module_system("import a from './a'; exports.a = a;", ModuleType::Js).await,
+        ModuleSystem::Hybrid
+    );
+    // locally declared `require` and properties named `exports` are not commonjs
+    assert_eq!(
+        parse_module_system(
+            "function require() {} require('./a'); const b = {}; b.exports = 1;",
+            ModuleType::Js
+        )
+        .await,
+        ModuleSystem::EsModule
+    );
+}
+
+#[tokio::test]
+async fn keep_comments_and_marks() {
+    let meta = parse("// leading\nexport const a = 1;", ModuleType::Ts)
+        .await
+        .unwrap()
+        .unwrap();
+    let script = meta.as_script();
+
+    assert_eq!(script.comments.leading.len(), 1);
+    assert_ne!(script.top_level_mark, script.unresolved_mark);
+}
+
+#[tokio::test]
+async fn report_parse_error_position() {
+    let Err(err) = parse("const a = 1;\nconst = 2;", ModuleType::Js).await else {
+        panic!("expect parse error");
+    };
+
+    match err {
+        CompilationError::ParseError { resolved_path, msg } => {
+            assert_eq!(resolved_path, "index");
+            assert!(msg.starts_with("index:2:7:"), "{}", msg);
+        }
+        _ => panic!("expect parse error, got {:?}", err),
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use toy_farm_core::{
+    error::Result, CompilationContext, Plugin, PluginEnforce, PluginHookContext,
+    PluginLoadHookParam, PluginLoadHookResult, PluginResolveHookParam, PluginResolveHookResult,
+    VIRTUAL_MODULE_PREFIX,
+};
+
+/// Built-in plugin that resolves and loads the modules registered in [CompilationContext::virtual_modules].
+/// Virtual modules that are not registered are left to the plugins who create them.
+pub struct FarmPluginVirtualModules {}
+
+impl FarmPluginVirtualModules {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for FarmPluginVirtualModules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for FarmPluginVirtualModules {
+    fn name(&self) -> &str {
+        "FarmPluginVirtualModules"
+    }
+
+    /// registered virtual modules never exist on the disk, resolve them before other plugins
+    fn enforce(&self) -> PluginEnforce {
+        PluginEnforce::Pre
+    }
+
+    async fn resolve(
+        &self,
+        param: Arc<PluginResolveHookParam>,
+        context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginResolveHookResult>> {
+        if !param.source.starts_with(VIRTUAL_MODULE_PREFIX)
+            || !context.virtual_modules.contains(&param.source)
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(PluginResolveHookResult {
+            resolved_path: param.source.clone(),
+            ..Default::default()
+        }))
+    }
+
+    async fn load(
+        &self,
+        param: Arc<PluginLoadHookParam>,
+        context: Arc<CompilationContext>,
+        _hook_context: Arc<PluginHookContext>,
+    ) -> Result<Option<PluginLoadHookResult>> {
+        if !param.resolved_path.starts_with(VIRTUAL_MODULE_PREFIX) {
+            return Ok(None);
+        }
+
+        Ok(context
+            .virtual_modules
+            .get(&param.resolved_path)
+            .map(|module| PluginLoadHookResult {
+                content: module.content.to_string(),
+                module_type: module.module_type,
+                source_map: None,
+            }))
+    }
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashMap, sync::Arc};
+
+use toy_farm_core::{
+    CompilationContext, Config, ModuleType, Plugin, PluginLoadHookParam, PluginResolveHookParam,
+    ResolveKind, VirtualModuleConfig,
+};
+use toy_farm_plugin_virtual_modules::FarmPluginVirtualModules;
+
+fn create_context() -> Arc<CompilationContext> {
+    let config = Config {
+        virtual_modules: HashMap::from([(
+            "virtual:config".to_string(),
+            VirtualModuleConfig {
+                content: "export default {}".to_string(),
+                module_type: ModuleType::Js,
+            },
+        )]),
+        ..Default::default()
+    };
+
+    Arc::new(CompilationContext::new(config, vec![]).unwrap())
+}
+
+async fn resolve(source: &str, context: &Arc<CompilationContext>) -> Option<String> {
+    FarmPluginVirtualModules::new()
+        .resolve(
+            Arc::new(PluginResolveHookParam {
+                source: source.to_string(),
+                importer: None,
+                kind: ResolveKind::Import,
+            }),
+            context.clone(),
+            Default::default(),
+        )
+        .await
+        .unwrap()
+        .map(|result| result.resolved_path)
+}
+
+async fn load(
+    resolved_path: &str,
+    context: &Arc<CompilationContext>,
+) -> Option<(String, ModuleType)> {
+    FarmPluginVirtualModules::new()
+        .load(
+            Arc::new(PluginLoadHookParam {
+                module_id: resolved_path.to_string(),
+                resolved_path: resolved_path.to_string(),
+                query: vec![],
+                meta: HashMap::new(),
+            }),
+            context.clone(),
+            Default::default(),
+        )
+        .await
+        .unwrap()
+        .map(|result| (result.content, result.module_type))
+}
+
+#[tokio::test]
+async fn resolve_and_load_registered_modules() {
+    let context = create_context();
+    context
+        .virtual_modules
+        .register("routes", ".app {}", ModuleType::Css);
+
+    assert_eq!(
+        resolve("virtual:config", &context).await,
+        Some("virtual:config".to_string())
+    );
+    assert_eq!(
+        load("virtual:config", &context).await,
+        Some(("export default {}".to_string(), ModuleType::Js))
+    );
+    assert_eq!(
+        load("virtual:routes", &context).await,
+        Some((".app {}".to_string(), ModuleType::Css))
+    );
+
+    context
+        .virtual_modules
+        .register("routes", ".page {}", ModuleType::Css);
+    assert_eq!(
+        load("virtual:routes", &context).await,
+        Some((".page {}".to_string(), ModuleType::Css))
+    );
+}
+
+#[tokio::test]
+async fn skip_unregistered_modules() {
+    let context = create_context();
+
+    // ids without the prefix are not virtual modules
+    assert_eq!(resolve("config", &context).await, None);
+    assert_eq!(resolve("virtual:unknown", &context).await, None);
+    assert_eq!(load("virtual:unknown", &context).await, None);
+}
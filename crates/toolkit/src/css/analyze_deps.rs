@@ -0,0 +1,53 @@
+use swc_css_ast::{ImportHref, ImportPrelude, Stylesheet, Url, UrlValue};
+use swc_css_visit::{Visit, VisitWith};
+use toy_farm_core::{PluginAnalyzeDepsHookResultEntry, ResolveKind};
+use toy_farm_utils::is_remote_or_inline_url;
+
+/// Analyze the dependencies of a css module, `@import './a.css'` is treated as [ResolveKind::CssAtImport]
+/// and `url('./a.png')` is treated as [ResolveKind::CssUrl]. Remote and inlined urls like `https://a.com/a.png` or `data:` are ignored.
+pub fn analyze_deps(ast: &Stylesheet) -> Vec<PluginAnalyzeDepsHookResultEntry> {
+    let mut analyzer = DepsAnalyzer { deps: vec![] };
+    ast.visit_with(&mut analyzer);
+
+    analyzer.deps
+}
+
+struct DepsAnalyzer {
+    deps: Vec<PluginAnalyzeDepsHookResultEntry>,
+}
+
+impl DepsAnalyzer {
+    fn insert_dep(&mut self, source: String, kind: ResolveKind) {
+        if !is_remote_or_inline_url(&source) {
+            self.deps
+                .push(PluginAnalyzeDepsHookResultEntry { source, kind });
+        }
+    }
+
+    fn get_url_value(url: &Url) -> Option<String> {
+        url.value.as_ref().map(|value| match &**value {
+            UrlValue::Str(str) => str.value.to_string(),
+            UrlValue::Raw(raw) => raw.value.to_string(),
+        })
+    }
+}
+
+impl Visit for DepsAnalyzer {
+    fn visit_import_prelude(&mut self, prelude: &ImportPrelude) {
+        // do not visit children here, the url of `@import url('./a.css')` is not a [ResolveKind::CssUrl]
+        let source = match &*prelude.href {
+            ImportHref::Url(url) => Self::get_url_value(url),
+            ImportHref::Str(str) => Some(str.value.to_string()),
+        };
+
+        if let Some(source) = source {
+            self.insert_dep(source, ResolveKind::CssAtImport);
+        }
+    }
+
+    fn visit_url(&mut self, url: &Url) {
+        if let Some(source) = Self::get_url_value(url) {
+            self.insert_dep(source, ResolveKind::CssUrl);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use swc_common::{comments::SingleThreadedComments, FileName, SourceMap};
+use swc_css_ast::Stylesheet;
+use swc_css_codegen::{
+    writer::basic::{BasicCssWriter, BasicCssWriterConfig},
+    CodeGenerator, CodegenConfig, Emit,
+};
+use swc_css_parser::{parse_file, parser::ParserConfig};
+use toy_farm_core::{error::Result, CompilationError};
+
+pub mod analyze_deps;
+
+pub use swc_css_ast;
+pub use swc_css_codegen;
+pub use swc_css_parser;
+pub use swc_css_visit;
+
+pub struct ParseCssModuleResult {
+    pub ast: Stylesheet,
+    pub comments: SingleThreadedComments,
+}
+
+/// parse the content of a css module to [Stylesheet], the first syntax error is reported as [CompilationError::ParseError]
+/// with the position of the error, for example: `/root/index.css:1:7: Expected ident`.
+pub fn parse_css_stylesheet(id: &str, content: &str) -> Result<ParseCssModuleResult> {
+    let cm = Arc::new(SourceMap::default());
+    let source_file = cm.new_source_file(FileName::Real(id.into()), content.to_string());
+    let comments = SingleThreadedComments::default();
+    let mut recovered_errors = vec![];
+
+    let to_parse_error = |e: swc_css_parser::error::Error| {
+        let msg = e.message().to_string();
+        let (span, _) = *e.into_inner();
+        let loc = cm.lookup_char_pos(span.lo);
+
+        CompilationError::ParseError {
+            resolved_path: id.to_string(),
+            msg: format!("{}:{}:{}: {}", id, loc.line, loc.col_display + 1, msg),
+        }
+    };
+
+    let ast = parse_file(
+        &source_file,
+        Some(&comments),
+        ParserConfig::default(),
+        &mut recovered_errors,
+    )
+    .map_err(to_parse_error)?;
+
+    if let Some(e) = recovered_errors.into_iter().next() {
+        return Err(to_parse_error(e));
+    }
+
+    Ok(ParseCssModuleResult { ast, comments })
+}
+
+/// generate css code of the [Stylesheet]
+pub fn codegen_css_stylesheet(stylesheet: &Stylesheet, minify: bool) -> String {
+    let mut css_code = String::new();
+    let writer = BasicCssWriter::new(&mut css_code, None, BasicCssWriterConfig::default());
+    let mut generator = CodeGenerator::new(writer, CodegenConfig { minify });
+
+    generator
+        .emit(stylesheet)
+        .expect("failed to generate css code");
+
+    css_code
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use toy_farm_core::ModuleType;
+
+/// Infer the module type from the extension of the path, mirroring the default `ResolveConfig.extensions`.
+/// Return [None] if the path has no extension.
+/// ```plain
+/// tsx -> Tsx
+/// ts, mts, cts -> Ts
+/// jsx -> Jsx
+/// js, mjs, cjs -> Js
+/// css -> Css
+/// html -> Html
+/// png, jpg, svg, ... -> Asset
+/// others, e.g. json -> Custom(ext)
+/// ```
+pub fn module_type_from_path(path: &str) -> Option<ModuleType> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+
+    let module_type = match ext.as_str() {
+        "tsx" => ModuleType::Tsx,
+        "ts" | "mts" | "cts" => ModuleType::Ts,
+        "jsx" => ModuleType::Jsx,
+        "js" | "mjs" | "cjs" => ModuleType::Js,
+        "css" => ModuleType::Css,
+        "html" | "htm" => ModuleType::Html,
+        ext if ASSET_EXTENSIONS.contains(&ext) => ModuleType::Asset,
+        ext => ModuleType::Custom(ext.to_string()),
+    };
+
+    Some(module_type)
+}
+
+const ASSET_EXTENSIONS: [&str; 16] = [
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "ico", "bmp", "svg", "woff", "woff2", "ttf",
+    "eot", "otf", "mp4", "mp3",
+];
+
+#[cfg(test)]
+mod tests {
+    use toy_farm_core::ModuleType;
+
+    use super::module_type_from_path;
+
+    #[test]
+    fn module_type() {
+        assert_eq!(
+            module_type_from_path("/root/index.tsx"),
+            Some(ModuleType::Tsx)
+        );
+        assert_eq!(
+            module_type_from_path("/root/index.mts"),
+            Some(ModuleType::Ts)
+        );
+        assert_eq!(
+            module_type_from_path("/root/index.cjs"),
+            Some(ModuleType::Js)
+        );
+        assert_eq!(
+            module_type_from_path("/root/index.jsx"),
+            Some(ModuleType::Jsx)
+        );
+        assert_eq!(
+            module_type_from_path("/root/index.css"),
+            Some(ModuleType::Css)
+        );
+        assert_eq!(
+            module_type_from_path("/root/index.html"),
+            Some(ModuleType::Html)
+        );
+        assert_eq!(
+            module_type_from_path("/root/logo.PNG"),
+            Some(ModuleType::Asset)
+        );
+        assert_eq!(
+            module_type_from_path("/root/package.json"),
+            Some(ModuleType::Custom("json".to_string()))
+        );
+        assert_eq!(module_type_from_path("/root/LICENSE"), None);
+    }
+}
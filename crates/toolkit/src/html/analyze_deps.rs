@@ -0,0 +1,63 @@
+use swc_html_ast::{Document, Element};
+use swc_html_visit::{Visit, VisitWith};
+use toy_farm_core::{PluginAnalyzeDepsHookResultEntry, ResolveKind};
+use toy_farm_utils::is_remote_or_inline_url;
+
+/// Analyze the dependencies of a html document, `<script src="./index.ts">` is treated as [ResolveKind::ScriptSrc]
+/// and `<link rel="stylesheet" href="./index.css">` is treated as [ResolveKind::LinkHref].
+/// Remote resources like `<script src="https://a.com/a.js">` are ignored.
+pub fn analyze_deps(document: &Document) -> Vec<PluginAnalyzeDepsHookResultEntry> {
+    let mut analyzer = DepsAnalyzer { deps: vec![] };
+    document.visit_with(&mut analyzer);
+
+    analyzer.deps
+}
+
+/// get the value of the specified attribute of the element
+pub fn get_element_attr<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|attr| &*attr.name == name)
+        .and_then(|attr| attr.value.as_deref())
+}
+
+struct DepsAnalyzer {
+    deps: Vec<PluginAnalyzeDepsHookResultEntry>,
+}
+
+impl DepsAnalyzer {
+    fn insert_dep(&mut self, source: &str, kind: ResolveKind) {
+        if !is_remote_or_inline_url(source) {
+            self.deps.push(PluginAnalyzeDepsHookResultEntry {
+                source: source.to_string(),
+                kind,
+            });
+        }
+    }
+}
+
+impl Visit for DepsAnalyzer {
+    fn visit_element(&mut self, element: &Element) {
+        match &*element.tag_name {
+            "script" => {
+                if let Some(src) = get_element_attr(element, "src") {
+                    self.insert_dep(src, ResolveKind::ScriptSrc);
+                }
+            }
+            "link" => {
+                let is_stylesheet = get_element_attr(element, "rel")
+                    .is_some_and(|rel| rel.split_whitespace().any(|r| r == "stylesheet"));
+
+                if is_stylesheet {
+                    if let Some(href) = get_element_attr(element, "href") {
+                        self.insert_dep(href, ResolveKind::LinkHref);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        element.visit_children_with(self);
+    }
+}
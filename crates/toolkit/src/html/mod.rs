@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use swc_common::{FileName, SourceMap};
+use swc_html_ast::{Child, Document, Element};
+use swc_html_codegen::{
+    writer::basic::{BasicHtmlWriter, BasicHtmlWriterConfig},
+    CodeGenerator, CodegenConfig, Emit,
+};
+use swc_html_parser::{parse_file_as_document, parser::ParserConfig};
+use swc_html_visit::{VisitMut, VisitMutWith};
+use toy_farm_core::{error::Result, CompilationError};
+
+pub mod analyze_deps;
+
+pub use swc_html_ast;
+pub use swc_html_codegen;
+pub use swc_html_parser;
+pub use swc_html_visit;
+
+/// parse the content of a html module to [Document]. Html is error tolerant, so only unrecoverable errors are reported as [CompilationError::ParseError]
+/// with the position of the error, for example: `/root/index.html:1:7: Unexpected end of file`.
+pub fn parse_html_document(id: &str, content: &str) -> Result<Document> {
+    let cm = Arc::new(SourceMap::default());
+    let source_file = cm.new_source_file(FileName::Real(id.into()), content.to_string());
+    let mut recovered_errors = vec![];
+
+    parse_file_as_document(&source_file, ParserConfig::default(), &mut recovered_errors).map_err(
+        |e| {
+            let msg = e.message().to_string();
+            let (span, _) = *e.into_inner();
+            let loc = cm.lookup_char_pos(span.lo);
+
+            CompilationError::ParseError {
+                resolved_path: id.to_string(),
+                msg: format!("{}:{}:{}: {}", id, loc.line, loc.col_display + 1, msg),
+            }
+        },
+    )
+}
+
+/// generate html code of the [Document]
+pub fn codegen_html_document(document: &Document, minify: bool) -> String {
+    let mut html_code = String::new();
+    let writer = BasicHtmlWriter::new(&mut html_code, None, BasicHtmlWriterConfig::default());
+    let mut generator = CodeGenerator::new(
+        writer,
+        CodegenConfig {
+            minify,
+            ..Default::default()
+        },
+    );
+
+    generator
+        .emit(document)
+        .expect("failed to generate html code");
+
+    html_code
+}
+
+/// remove whitespace-only text that does not affect rendering: all of them in `<html>` and `<head>`,
+/// and the leading and trailing ones in `<body>`. Used to minify the html before codegen.
+pub fn remove_insignificant_whitespace(document: &mut Document) {
+    document.visit_mut_with(&mut WhitespaceRemover);
+}
+
+struct WhitespaceRemover;
+
+impl WhitespaceRemover {
+    fn is_whitespace(child: &Child) -> bool {
+        matches!(child, Child::Text(text) if text.data.trim().is_empty())
+    }
+}
+
+impl VisitMut for WhitespaceRemover {
+    fn visit_mut_element(&mut self, element: &mut Element) {
+        match &*element.tag_name {
+            "html" | "head" => element.children.retain(|child| !Self::is_whitespace(child)),
+            "body" => {
+                while element.children.last().is_some_and(Self::is_whitespace) {
+                    element.children.pop();
+                }
+
+                let leading = element
+                    .children
+                    .iter()
+                    .take_while(|child| Self::is_whitespace(child))
+                    .count();
+                element.children.drain(..leading);
+            }
+            _ => {}
+        }
+
+        element.visit_mut_children_with(self);
+    }
+}
@@ -1,3 +1,9 @@
 mod hash;
 
+pub mod css;
+pub mod fs;
+pub mod html;
+pub mod script;
+pub mod sourcemap;
+
 pub use hash::*;
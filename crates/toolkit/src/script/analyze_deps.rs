@@ -0,0 +1,166 @@
+use swc_common::{Mark, SyntaxContext};
+use swc_ecma_ast::{
+    CallExpr, Callee, ExportAll, Expr, ImportDecl, Lit, Module as SwcModule, NamedExport,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+use toy_farm_core::{PluginAnalyzeDepsHookResultEntry, ResolveKind};
+
+/// Analyze the dependencies of a script module, the result keeps the order of the original source.
+/// for example:
+/// ```js
+/// import a from './a';
+/// export * from './b';
+/// import('./c');
+/// require('./d');
+/// ```
+/// return `[('./a', Import), ('./b', ExportFrom), ('./c', DynamicImport), ('./d', Require)]`.
+///
+/// Type only imports and exports (`import type { A } from './a'`) are ignored as they are erased after compilation.
+/// Only the unresolved `require` is treated as a dependency, a local binding named `require` is not.
+///
+/// Note that this function must be called under the same [swc_common::GLOBALS] as the `unresolved_mark`, after the ast is resolved by [swc_ecma_transforms_base::resolver].
+pub fn analyze_deps(
+    ast: &SwcModule,
+    unresolved_mark: Mark,
+) -> Vec<PluginAnalyzeDepsHookResultEntry> {
+    let mut analyzer = DepsAnalyzer {
+        deps: vec![],
+        unresolved_ctxt: SyntaxContext::empty().apply_mark(unresolved_mark),
+    };
+    ast.visit_with(&mut analyzer);
+
+    analyzer.deps
+}
+
+struct DepsAnalyzer {
+    deps: Vec<PluginAnalyzeDepsHookResultEntry>,
+    unresolved_ctxt: SyntaxContext,
+}
+
+impl DepsAnalyzer {
+    fn insert_dep(&mut self, source: String, kind: ResolveKind) {
+        self.deps
+            .push(PluginAnalyzeDepsHookResultEntry { source, kind });
+    }
+
+    /// get the source of `import('./a')` or `require('./a')`, only string literal and template literal without expressions are supported
+    fn get_call_source(call_expr: &CallExpr) -> Option<String> {
+        if call_expr.args.len() != 1 || call_expr.args[0].spread.is_some() {
+            return None;
+        }
+
+        match &*call_expr.args[0].expr {
+            Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+            Expr::Tpl(tpl) if tpl.exprs.is_empty() && tpl.quasis.len() == 1 => tpl.quasis[0]
+                .cooked
+                .as_ref()
+                .map(|cooked| cooked.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Visit for DepsAnalyzer {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        if !import.type_only {
+            self.insert_dep(import.src.value.to_string(), ResolveKind::Import);
+        }
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        if !export.type_only {
+            self.insert_dep(export.src.value.to_string(), ResolveKind::ExportFrom);
+        }
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            if !export.type_only {
+                self.insert_dep(src.value.to_string(), ResolveKind::ExportFrom);
+            }
+        }
+    }
+
+    fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+        let kind = match &call_expr.callee {
+            Callee::Import(_) => Some(ResolveKind::DynamicImport),
+            Callee::Expr(expr) => match &**expr {
+                Expr::Ident(ident)
+                    if &*ident.sym == "require" && ident.span.ctxt == self.unresolved_ctxt =>
+                {
+                    Some(ResolveKind::Require)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            if let Some(source) = Self::get_call_source(call_expr) {
+                self.insert_dep(source, kind);
+            }
+        }
+
+        call_expr.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_common::{FileName, Mark, SourceMap, GLOBALS};
+    use swc_ecma_parser::{parse_file_as_module, Syntax, TsConfig};
+    use swc_ecma_transforms_base::resolver;
+    use swc_ecma_visit::VisitMutWith;
+    use toy_farm_core::{PluginAnalyzeDepsHookResultEntry, ResolveKind};
+
+    use super::analyze_deps;
+
+    #[test]
+    fn analyze_script_deps() {
+        let code = r#"
+import a from './a';
+import type { B } from './b';
+export * from './c';
+export { d } from './d';
+const e = import('./e');
+const f = require(`./f`);
+const g = require(unknown);
+function load(require) { return require('./h'); }
+{ function require() {} require('./i'); }
+"#;
+        let cm = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Anon, code.to_string());
+        let mut ast = parse_file_as_module(
+            &fm,
+            Syntax::Typescript(TsConfig::default()),
+            Default::default(),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+
+        let dep = |source: &str, kind: ResolveKind| PluginAnalyzeDepsHookResultEntry {
+            source: source.to_string(),
+            kind,
+        };
+
+        GLOBALS.set(&Default::default(), || {
+            let unresolved_mark = Mark::new();
+            ast.visit_mut_with(&mut resolver(unresolved_mark, Mark::new(), true));
+
+            // `require` bound by the parameter and the local function is not a dependency
+            assert_eq!(
+                analyze_deps(&ast, unresolved_mark),
+                vec![
+                    dep("./a", ResolveKind::Import),
+                    dep("./c", ResolveKind::ExportFrom),
+                    dep("./d", ResolveKind::ExportFrom),
+                    dep("./e", ResolveKind::DynamicImport),
+                    dep("./f", ResolveKind::Require),
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use swc_common::{
+    comments::SingleThreadedComments, FileName, Mark, SourceMap, Spanned, SyntaxContext,
+};
+use swc_ecma_ast::{EsVersion, Ident, MemberExpr, Module as SwcModule, ModuleItem};
+use swc_ecma_codegen::{text_writer::JsWriter, Config as CodegenConfig, Emitter};
+use swc_ecma_parser::{parse_file_as_module, EsConfig, Syntax, TsConfig};
+use swc_ecma_visit::{Visit, VisitWith};
+use toy_farm_core::{error::Result, CompilationError, ModuleSystem, ModuleType};
+
+pub mod analyze_deps;
+
+pub use swc_common;
+pub use swc_ecma_ast;
+pub use swc_ecma_codegen;
+pub use swc_ecma_parser;
+pub use swc_ecma_transforms_base;
+pub use swc_ecma_transforms_module;
+pub use swc_ecma_transforms_typescript;
+pub use swc_ecma_visit;
+
+pub struct ParseScriptModuleResult {
+    pub ast: SwcModule,
+    pub comments: SingleThreadedComments,
+}
+
+/// parse the content of a module to [SwcModule] ast, the first syntax error is reported as [CompilationError::ParseError]
+/// with the position of the error, for example: `/root/index.ts:1:7: Expected ident`.
+pub fn parse_module(
+    id: &str,
+    content: &str,
+    syntax: Syntax,
+    target: EsVersion,
+) -> Result<ParseScriptModuleResult> {
+    let cm = Arc::new(SourceMap::default());
+    let source_file = cm.new_source_file(FileName::Real(id.into()), content.to_string());
+    let comments = SingleThreadedComments::default();
+    let mut recovered_errors = vec![];
+
+    let to_parse_error = |span: swc_common::Span, msg: String| {
+        let loc = cm.lookup_char_pos(span.lo);
+
+        CompilationError::ParseError {
+            resolved_path: id.to_string(),
+            msg: format!("{}:{}:{}: {}", id, loc.line, loc.col_display + 1, msg),
+        }
+    };
+
+    let ast = parse_file_as_module(
+        &source_file,
+        syntax,
+        target,
+        Some(&comments),
+        &mut recovered_errors,
+    )
+    .map_err(|e| to_parse_error(e.span(), e.kind().msg().to_string()))?;
+
+    if let Some(e) = recovered_errors.into_iter().next() {
+        return Err(to_parse_error(e.span(), e.kind().msg().to_string()));
+    }
+
+    Ok(ParseScriptModuleResult { ast, comments })
+}
+
+/// generate js code of the [SwcModule]
+pub fn codegen_module(ast: &SwcModule, minify: bool) -> String {
+    let cm = Arc::new(SourceMap::default());
+    let mut buf = vec![];
+
+    {
+        let writer = JsWriter::new(cm.clone(), "\n", &mut buf, None);
+        let mut emitter = Emitter {
+            cfg: CodegenConfig::default().with_minify(minify),
+            cm,
+            comments: None,
+            wr: writer,
+        };
+
+        emitter
+            .emit_module(ast)
+            .expect("failed to generate js code");
+    }
+
+    String::from_utf8(buf).expect("the generated js code is not utf8")
+}
+
+/// Get the swc [Syntax] of the script module type, return [None] if the module type is not a script
+pub fn syntax_from_module_type(module_type: &ModuleType) -> Option<Syntax> {
+    match module_type {
+        ModuleType::Js => Some(Syntax::Es(EsConfig {
+            jsx: false,
+            import_attributes: true,
+            ..Default::default()
+        })),
+        ModuleType::Jsx => Some(Syntax::Es(EsConfig {
+            jsx: true,
+            import_attributes: true,
+            ..Default::default()
+        })),
+        ModuleType::Ts => Some(Syntax::Typescript(TsConfig {
+            tsx: false,
+            decorators: true,
+            ..Default::default()
+        })),
+        ModuleType::Tsx => Some(Syntax::Typescript(TsConfig {
+            tsx: true,
+            decorators: true,
+            ..Default::default()
+        })),
+        _ => None,
+    }
+}
+
+/// Detect the module system of the ast, `import`/`export` means [ModuleSystem::EsModule],
+/// using unresolved `module`, `exports` or `require` means [ModuleSystem::CommonJs], both means [ModuleSystem::Hybrid].
+///
+/// Note that this function must be called under the same [swc_common::GLOBALS] as the `unresolved_mark`, after the ast is resolved by [swc_ecma_transforms_base::resolver].
+pub fn module_system_from_ast(ast: &SwcModule, unresolved_mark: Mark) -> ModuleSystem {
+    let has_module_decl = ast
+        .body
+        .iter()
+        .any(|item| matches!(item, ModuleItem::ModuleDecl(_)));
+
+    let mut cjs_detector = CommonJsDetector {
+        unresolved_ctxt: SyntaxContext::empty().apply_mark(unresolved_mark),
+        is_commonjs: false,
+    };
+    ast.visit_with(&mut cjs_detector);
+
+    match (has_module_decl, cjs_detector.is_commonjs) {
+        (true, true) => ModuleSystem::Hybrid,
+        (false, true) => ModuleSystem::CommonJs,
+        _ => ModuleSystem::EsModule,
+    }
+}
+
+struct CommonJsDetector {
+    unresolved_ctxt: SyntaxContext,
+    is_commonjs: bool,
+}
+
+impl Visit for CommonJsDetector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if ident.span.ctxt == self.unresolved_ctxt
+            && matches!(&*ident.sym, "module" | "exports" | "require")
+        {
+            self.is_commonjs = true;
+        }
+    }
+
+    fn visit_member_expr(&mut self, member_expr: &MemberExpr) {
+        // skip the property, `a.exports` should not be treated as commonjs
+        member_expr.obj.visit_with(self);
+
+        if member_expr.prop.is_computed() {
+            member_expr.prop.visit_with(self);
+        }
+    }
+}
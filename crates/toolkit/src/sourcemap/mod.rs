@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use sourcemap::{SourceMap, SourceMapBuilder};
+use toy_farm_core::{error::Result, CompilationError};
+
+pub use sourcemap;
+
+/// Collapse the source map chain of a module into one source map, which maps the final content to the original source.
+/// The chain is in transform order, the first source map maps the original source to the output of the first transform,
+/// and the last one maps the output of the previous transform to the final content.
+///
+/// A mapping of the final content is dropped if it can not be traced back to the original source.
+pub fn collapse_sourcemap_chain(chain: &[Arc<String>]) -> Result<String> {
+    let maps = chain
+        .iter()
+        .map(|map| {
+            SourceMap::from_slice(map.as_bytes()).map_err(|e| {
+                CompilationError::GenericError(format!("Failed to parse source map: {}", e))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Some((last, previous)) = maps.split_last() else {
+        return Err(CompilationError::GenericError(
+            "Can not collapse an empty source map chain".to_string(),
+        ));
+    };
+
+    let mut builder = SourceMapBuilder::new(last.get_file());
+
+    for token in last.tokens() {
+        let mut original = Some(token);
+
+        for map in previous.iter().rev() {
+            let Some(prev) = original else {
+                break;
+            };
+            // only trust tokens on the same line, the nearest token of a previous line maps unrelated code
+            original = map
+                .lookup_token(prev.get_src_line(), prev.get_src_col())
+                .filter(|t| t.get_dst_line() == prev.get_src_line());
+        }
+
+        let Some(original) = original else {
+            continue;
+        };
+        let raw = builder.add(
+            token.get_dst_line(),
+            token.get_dst_col(),
+            original.get_src_line(),
+            original.get_src_col(),
+            original.get_source(),
+            original.get_name(),
+            false,
+        );
+
+        if raw.src_id != !0 && builder.get_source_contents(raw.src_id).is_none() {
+            let contents = original.get_source_view().map(|view| view.source());
+            builder.set_source_contents(raw.src_id, contents);
+        }
+    }
+
+    let mut buf = vec![];
+    builder.into_sourcemap().to_writer(&mut buf).map_err(|e| {
+        CompilationError::GenericError(format!("Failed to write source map: {}", e))
+    })?;
+
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sourcemap::{SourceMap, SourceMapBuilder};
+
+    use super::collapse_sourcemap_chain;
+
+    fn build_map(
+        source: &str,
+        contents: Option<&str>,
+        tokens: &[(u32, u32, u32, u32)],
+    ) -> Arc<String> {
+        let mut builder = SourceMapBuilder::new(None);
+
+        for (dst_line, dst_col, src_line, src_col) in tokens {
+            let raw = builder.add(
+                *dst_line,
+                *dst_col,
+                *src_line,
+                *src_col,
+                Some(source),
+                None,
+                false,
+            );
+            builder.set_source_contents(raw.src_id, contents);
+        }
+
+        let mut buf = vec![];
+        builder.into_sourcemap().to_writer(&mut buf).unwrap();
+        Arc::new(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn collapse_chain() {
+        // original -> first transform: line 0 moves to line 1
+        let first = build_map(
+            "index.ts",
+            Some("const a = 1;"),
+            &[(1, 0, 0, 0), (1, 6, 0, 6)],
+        );
+        // first transform -> second transform: columns are shifted by 2
+        let second = build_map(
+            "index.js",
+            None,
+            &[(1, 2, 1, 0), (1, 8, 1, 6), (2, 0, 5, 0)],
+        );
+
+        let collapsed = collapse_sourcemap_chain(&[first, second]).unwrap();
+        let map = SourceMap::from_slice(collapsed.as_bytes()).unwrap();
+        let tokens = map
+            .tokens()
+            .map(|t| {
+                (
+                    t.get_dst_line(),
+                    t.get_dst_col(),
+                    t.get_src_line(),
+                    t.get_src_col(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // the mapping of line 2 can not be traced back to the original source
+        assert_eq!(tokens, vec![(1, 2, 0, 0), (1, 8, 0, 6)]);
+        assert_eq!(map.get_source(0), Some("index.ts"));
+        assert_eq!(map.get_source_contents(0), Some("const a = 1;"));
+    }
+}
@@ -22,8 +22,90 @@ pub fn stringify_query(query: &Vec<(String, String)>) -> String {
     format!("?{}", qs.join("&"))
 }
 
+/**
+ * Parse the query string back to query, the reverse of [stringify_query]
+ * # Examples: "?a=1&b" => vec![("a".to_string(), "1".to_string()), ("b".to_string(), "".to_string())]
+ */
+pub fn parse_query(query_string: &str) -> Vec<(String, String)> {
+    query_string
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (pair.to_string(), PARSE_QUERY_TRUE.to_string()),
+        })
+        .collect()
+}
+
 pub fn transform_string_to_static_str(s: String) -> &'static str {
     Box::leak(s.into_boxed_str())
 }
 
 pub mod hash;
+
+/// Whether the url points to a remote or inlined resource that should not be resolved, e.g. `https://a.com/a.png`, `//a.com/a.png`, `data:image/png;base64,...` or `#id`
+pub fn is_remote_or_inline_url(url: &str) -> bool {
+    url.starts_with("http://")
+        || url.starts_with("https://")
+        || url.starts_with("//")
+        || url.starts_with("data:")
+        || url.starts_with('#')
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    use base64::Engine;
+
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Normalize the path lexically without accessing the file system, `.` segments are removed and `..` segments pop their parents.
+/// # Examples: "/root/a/./b/../c.js" => "/root/a/c.js"
+pub fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
+    use std::path::Component;
+
+    let mut result = std::path::PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push(component);
+                }
+            }
+            _ => result.push(component),
+        }
+    }
+
+    result
+}
+
+/// The real path of `path` with the symlinks resolved if `symlinks` is true, otherwise the normalized path keeping the links.
+/// If the file does not exist, e.g. it is removed, the closest existing ancestor is resolved and the rest of the path is joined,
+/// so that the removed file gets the same path as before.
+pub fn canonicalize_path(path: &std::path::Path, symlinks: bool) -> std::path::PathBuf {
+    if !symlinks {
+        return normalize_path(path);
+    }
+
+    if let Ok(real_path) = std::fs::canonicalize(path) {
+        return real_path;
+    }
+
+    let path = normalize_path(path);
+
+    for ancestor in path.ancestors() {
+        if let Ok(real_path) = std::fs::canonicalize(ancestor) {
+            let rest = path.strip_prefix(ancestor).unwrap();
+
+            return if rest.as_os_str().is_empty() {
+                real_path
+            } else {
+                real_path.join(rest)
+            };
+        }
+    }
+
+    path
+}